@@ -1,26 +1,92 @@
-fn add_calories_for_elf(elf_carry: &str) -> u32 {
-        elf_carry
-        .lines()
-        .map(|item| item.parse::<u32>().unwrap())
-        .sum::<u32>()
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Elf {
+    index: usize,
+    calories: u32,
+}
+
+/// Read elves' total calories from a stream a line at a time, so inputs
+/// never have to be held in memory. Runs of blank lines (or lines of just
+/// whitespace) all count as a single separator between elves.
+fn elves<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Elf, String>> {
+    let mut lines = reader.lines().enumerate();
+    let mut index = 0;
+    std::iter::from_fn(move || {
+        let mut calories: Option<u32> = None;
+        for (line_idx, line) in lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(format!("line {}: {}", line_idx + 1, e))),
+            };
+            let item = line.trim();
+            if item.is_empty() {
+                if calories.is_some() {
+                    break;
+                }
+                continue;
+            }
+            let total = item
+                .parse::<u32>()
+                .map_err(|_| format!("line {}: \"{}\" is not a calorie count", line_idx + 1, item))
+                .and_then(|item| {
+                    calories.unwrap_or(0).checked_add(item).ok_or(format!(
+                        "line {}: elf {} carries too many calories",
+                        line_idx + 1,
+                        index
+                    ))
+                });
+            match total {
+                Ok(total) => calories = Some(total),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let elf = Elf {
+            index,
+            calories: calories?,
+        };
+        index += 1;
+        Some(Ok(elf))
+    })
+}
+
+/// Return the `k` elves carrying the most calories, most first. Only `k`
+/// elves are held at a time so this is O(n log k)
+fn top_k(elves: impl Iterator<Item = Result<Elf, String>>, k: usize) -> Result<Vec<Elf>, String> {
+    // Min-heap on calories, preferring to keep earlier elves on a tie
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for elf in elves {
+        let elf = elf?;
+        heap.push(Reverse((elf.calories, Reverse(elf.index))));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((calories, Reverse(index)))| Elf { index, calories })
+        .collect())
+}
+
+/// Total calories of the top `k` elves, or `None` if there are no elves
+fn top_total(input: &str, k: usize) -> Option<u32> {
+    let top = top_k(elves(input.as_bytes()), k).unwrap();
+    if top.is_empty() {
+        return None;
+    }
+    Some(top.iter().map(|elf| elf.calories).sum())
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    input
-        .split("\n\n")
-        .map(add_calories_for_elf)
-        .max()
+    top_total(input, 1)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let mut result = input
-        .split("\n\n")
-        .map(add_calories_for_elf)
-        .collect::<Vec<_>>();
-    result.sort_by(|a,b| b.cmp(a));
-    let sum: u32 = result.iter().take(3).sum();
-    Some(sum)
- }
+    top_total(input, 3)
+}
 
 fn main() {
     let input = &aoc::read_file("inputs", 1);
@@ -31,6 +97,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::{BufReader, Read};
 
     #[test]
     fn test_part_one() {
@@ -46,13 +113,134 @@ mod tests {
 8305
 10764
 11568
-3353".to_owned();
+3353"
+            .to_owned();
         assert_eq!(part_one(&input), Some(53636));
     }
 
     #[test]
     fn test_part_two() {
         let input = aoc::read_file("examples", 1);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(101671));
+    }
+
+    #[test]
+    fn test_top_k() {
+        let input = aoc::read_file("examples", 1);
+        let top = top_k(elves(input.as_bytes()), 4).unwrap();
+        assert_eq!(
+            top,
+            vec![
+                Elf {
+                    index: 7,
+                    calories: 53636
+                },
+                Elf {
+                    index: 5,
+                    calories: 24035
+                },
+                Elf {
+                    index: 3,
+                    calories: 24000
+                },
+                Elf {
+                    index: 6,
+                    calories: 22350
+                },
+            ]
+        );
+        assert_eq!(top_k(elves(input.as_bytes()), 0).unwrap(), vec![]);
+        assert_eq!(top_k(elves(input.as_bytes()), 100).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_ties_keep_first_elf() {
+        let top = top_k(elves("5\n\n7\n\n5\n\n5".as_bytes()), 2).unwrap();
+        assert_eq!(
+            top,
+            vec![
+                Elf {
+                    index: 1,
+                    calories: 7
+                },
+                Elf {
+                    index: 0,
+                    calories: 5
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blank_padding() {
+        let input = "\n\n  1000\n2000 \n\n \n\n\n3000\n\n\n";
+        let all = elves(input.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![
+                Elf {
+                    index: 0,
+                    calories: 3000
+                },
+                Elf {
+                    index: 1,
+                    calories: 3000
+                }
+            ]
+        );
+        // Without any elves there's no answer
+        assert_eq!(part_one(""), None);
+        assert_eq!(part_two("\n \n\n"), None);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let result = top_k(elves("1000\n\n20x0\n".as_bytes()), 3);
+        assert_eq!(
+            result,
+            Err("line 3: \"20x0\" is not a calorie count".to_owned())
+        );
+        let result = top_k(elves("4000000000\n400000000\n".as_bytes()), 3);
+        assert_eq!(
+            result,
+            Err("line 2: elf 0 carries too many calories".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        // A hundred thousand elves, generated on the fly rather than held in memory
+        let elves_input =
+            (0..100_000u32).flat_map(|i| format!("{}\n{}\n\n", i % 1000, i / 1000).into_bytes());
+        let reader = BufReader::new(IterReader(elves_input));
+        let top = top_k(elves(reader), 2).unwrap();
+        assert_eq!(
+            top,
+            vec![
+                Elf {
+                    index: 99999,
+                    calories: 1098
+                },
+                Elf {
+                    index: 98999,
+                    calories: 1097
+                },
+            ]
+        );
+    }
+
+    struct IterReader<I>(I);
+
+    impl<I: Iterator<Item = u8>> Read for IterReader<I> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let mut count = 0;
+            for (slot, byte) in buf.iter_mut().zip(&mut self.0) {
+                *slot = byte;
+                count += 1;
+            }
+            Ok(count)
+        }
     }
 }
@@ -1,23 +1,8 @@
 use std::str::FromStr;
 
-enum Move {
-    Rock = 1,
-    Paper = 2,
-    Scissors = 3,
-}
-impl FromStr for Move {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" | "X" => Ok(Move::Rock),
-            "B" | "Y" => Ok(Move::Paper),
-            "C" | "Z" => Ok(Move::Scissors),
-            _ => Err("Not a known move".to_owned())
-        }
-    }
-}
+use itertools::Itertools;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Outcome {
     Lose = 0,
     Draw = 3,
@@ -31,78 +16,329 @@ impl FromStr for Outcome {
             "X" => Ok(Outcome::Lose),
             "Y" => Ok(Outcome::Draw),
             "Z" => Ok(Outcome::Win),
-            _ => Err("Not a known outcome".to_owned())
+            _ => Err(format!("\"{}\" is not a known outcome", s)),
         }
     }
 }
 
+/// A game as data: the moves with their scores, which moves beat which, and
+/// the letters the strategy guide uses for each player's moves
+#[derive(Debug)]
+struct Rules {
+    names: Vec<&'static str>,
+    scores: Vec<u32>,
+    // beats[a][b] is true if move a beats move b
+    beats: Vec<Vec<bool>>,
+    their_letters: Vec<char>,
+    my_letters: Vec<char>,
+}
 
-fn line_to_score(input: &str) -> u32 {
-    let mut split = input.split(" ");
-    let their_move = split.next().unwrap().parse::<Move>().unwrap();
-    let my_move =  split.next().unwrap().parse::<Move>().unwrap();
-    let outcome = match (&their_move, &my_move) {
-        (Move::Rock, Move::Rock) => Outcome::Draw,
-        (Move::Rock, Move::Paper) => Outcome::Win,
-        (Move::Rock, Move::Scissors) => Outcome::Lose,
+impl Rules {
+    fn new(
+        moves: &[(&'static str, u32)],
+        beats: &[(&str, &str)],
+        their_letters: &str,
+        my_letters: &str,
+    ) -> Result<Self, String> {
+        let names = moves.iter().map(|&(name, _)| name).collect_vec();
+        let index = |name: &str| {
+            names
+                .iter()
+                .position(|&n| n == name)
+                .ok_or(format!("unknown move \"{}\"", name))
+        };
+        let mut table = vec![vec![false; names.len()]; names.len()];
+        for &(winner, loser) in beats {
+            let (winner, loser) = (index(winner)?, index(loser)?);
+            if winner == loser || table[loser][winner] {
+                return Err(format!("{} can't beat {}", names[winner], names[loser]));
+            }
+            table[winner][loser] = true;
+        }
+        let their_letters = their_letters.chars().collect_vec();
+        let my_letters = my_letters.chars().collect_vec();
+        if their_letters.len() != names.len() || my_letters.len() != names.len() {
+            return Err("every move needs a letter for each player".to_owned());
+        }
+        Ok(Rules {
+            names,
+            scores: moves.iter().map(|&(_, score)| score).collect(),
+            beats: table,
+            their_letters,
+            my_letters,
+        })
+    }
 
-        (Move::Paper, Move::Rock) => Outcome::Lose,
-        (Move::Paper, Move::Paper) => Outcome::Draw,
-        (Move::Paper, Move::Scissors) => Outcome::Win,
+    fn classic() -> Self {
+        Rules::new(
+            &[("rock", 1), ("paper", 2), ("scissors", 3)],
+            &[
+                ("rock", "scissors"),
+                ("paper", "rock"),
+                ("scissors", "paper"),
+            ],
+            "ABC",
+            "XYZ",
+        )
+        .unwrap()
+    }
 
-        (Move::Scissors, Move::Rock) => Outcome::Win,
-        (Move::Scissors, Move::Paper) => Outcome::Lose,
-        (Move::Scissors, Move::Scissors) => Outcome::Draw,
-    };
-    my_move as u32 + outcome as u32
+    fn lizard_spock() -> Self {
+        Rules::new(
+            &[
+                ("rock", 1),
+                ("paper", 2),
+                ("scissors", 3),
+                ("lizard", 4),
+                ("spock", 5),
+            ],
+            &[
+                ("scissors", "paper"),
+                ("paper", "rock"),
+                ("rock", "lizard"),
+                ("lizard", "spock"),
+                ("spock", "scissors"),
+                ("scissors", "lizard"),
+                ("lizard", "paper"),
+                ("paper", "spock"),
+                ("spock", "rock"),
+                ("rock", "scissors"),
+            ],
+            "ABCDE",
+            "VWXYZ",
+        )
+        .unwrap()
+    }
+
+    fn outcome(&self, mine: usize, theirs: usize) -> Outcome {
+        if self.beats[mine][theirs] {
+            Outcome::Win
+        } else if self.beats[theirs][mine] {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    /// The best scoring move that gets `outcome` against `theirs`
+    fn move_for(&self, theirs: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.names.len())
+            .filter(|&mine| self.outcome(mine, theirs) == outcome)
+            .max_by_key(|&mine| self.scores[mine])
+    }
 }
 
-fn work_out_score(input: &str) -> u32 {
-    let mut split = input.split(" ");
-    let their_move = split.next().unwrap().parse::<Move>().unwrap();
-    let outcome =  split.next().unwrap().parse::<Outcome>().unwrap();
-    let my_move = match (&their_move, &outcome) {
-        (Move::Rock, Outcome::Draw) => Move::Rock,
-        (Move::Rock, Outcome::Win) => Move::Paper,
-        (Move::Rock, Outcome::Lose) => Move::Scissors,
+impl FromStr for Rules {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" | "rps" => Ok(Rules::classic()),
+            "rpsls" | "lizard-spock" => Ok(Rules::lizard_spock()),
+            _ => Err(format!("\"{}\" is not a known game", s)),
+        }
+    }
+}
 
-        (Move::Paper, Outcome::Lose) => Move::Rock,
-        (Move::Paper, Outcome::Draw) => Move::Paper,
-        (Move::Paper, Outcome::Win) => Move::Scissors,
+/// How to read the second column of the strategy guide
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Strategy {
+    /// The move to play
+    Moves,
+    /// The outcome to aim for
+    Outcomes,
+}
 
-        (Move::Scissors,  Outcome::Win) => Move::Rock,
-        (Move::Scissors,  Outcome::Lose) => Move::Paper,
-        (Move::Scissors,  Outcome::Draw) => Move::Scissors,
-    };
-    my_move as u32 + outcome as u32
+impl FromStr for Strategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moves" => Ok(Strategy::Moves),
+            "outcomes" => Ok(Strategy::Outcomes),
+            _ => Err(format!("\"{}\" is not a known strategy", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Round {
+    theirs: usize,
+    column: char,
+}
+
+fn parse_guide(rules: &Rules, input: &str) -> Result<Vec<Round>, String> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            let letters = line.split_whitespace().collect_vec();
+            let (theirs, column) = match letters[..] {
+                [theirs, column] if theirs.len() == 1 && column.len() == 1 => (
+                    theirs.chars().next().unwrap(),
+                    column.chars().next().unwrap(),
+                ),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected two letters, got \"{}\"",
+                        idx + 1,
+                        line
+                    ))
+                }
+            };
+            let theirs = rules
+                .their_letters
+                .iter()
+                .position(|&c| c == theirs)
+                .ok_or(format!(
+                    "line {}: \"{}\" is not a known move",
+                    idx + 1,
+                    theirs
+                ))?;
+            Ok(Round { theirs, column })
+        })
+        .collect()
+}
+
+/// Scores for a game, split by outcome
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+struct Tally {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    /// Points from the outcomes alone, as (win, draw) since losing scores nothing
+    outcome_points: (u32, u32),
+    move_points: u32,
+}
+
+impl Tally {
+    fn add(&mut self, rules: &Rules, mine: usize, theirs: usize) {
+        let outcome = rules.outcome(mine, theirs);
+        match outcome {
+            Outcome::Win => {
+                self.wins += 1;
+                self.outcome_points.0 += outcome as u32;
+            }
+            Outcome::Draw => {
+                self.draws += 1;
+                self.outcome_points.1 += outcome as u32;
+            }
+            Outcome::Lose => self.losses += 1,
+        }
+        self.move_points += rules.scores[mine];
+    }
+
+    fn total(&self) -> u32 {
+        self.move_points + self.outcome_points.0 + self.outcome_points.1
+    }
+}
+
+fn play(rules: &Rules, guide: &[Round], strategy: Strategy) -> Result<Tally, String> {
+    let mut tally = Tally::default();
+    for round in guide {
+        let mine = match strategy {
+            Strategy::Moves => rules
+                .my_letters
+                .iter()
+                .position(|&c| c == round.column)
+                .ok_or(format!("\"{}\" is not a known move", round.column))?,
+            Strategy::Outcomes => {
+                let outcome = round.column.to_string().parse::<Outcome>()?;
+                rules.move_for(round.theirs, outcome).ok_or(format!(
+                    "no move gets {:?} against {}",
+                    outcome, rules.names[round.theirs]
+                ))?
+            }
+        };
+        tally.add(rules, mine, round.theirs);
+    }
+    Ok(tally)
+}
+
+/// Find the reading of our letters as moves that scores the most for this
+/// guide, returning the move for each letter and the resulting tally
+fn optimal_strategy(rules: &Rules, guide: &[Round]) -> (Vec<usize>, Tally) {
+    (0..rules.names.len())
+        .permutations(rules.names.len())
+        .map(|assignment| {
+            let mut tally = Tally::default();
+            for round in guide {
+                let letter = rules.my_letters.iter().position(|&c| c == round.column);
+                if let Some(letter) = letter {
+                    tally.add(rules, assignment[letter], round.theirs);
+                }
+            }
+            (assignment, tally)
+        })
+        .max_by_key(|(_, tally)| tally.total())
+        .unwrap()
+}
+
+fn total_score(input: &str, strategy: Strategy) -> Option<u32> {
+    let rules = Rules::classic();
+    let guide = parse_guide(&rules, input).unwrap();
+    Some(play(&rules, &guide, strategy).unwrap().total())
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let result = input
-      .lines()
-      .map(line_to_score)
-      .sum::<u32>();
-    Some(result)
+    total_score(input, Strategy::Moves)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let result = input
-      .lines()
-      .map(work_out_score)
-      .sum::<u32>();
-    Some(result)
+    total_score(input, Strategy::Outcomes)
+}
+
+/// Report on a guide for any game and reading of it, chosen with
+/// `--rules classic|rpsls` and `--strategy moves|outcomes`
+fn report(input: &str, rules: &Rules, strategy: Strategy) {
+    let guide = match parse_guide(rules, input) {
+        Ok(guide) => guide,
+        Err(e) => return println!("can't read the guide: {}", e),
+    };
+    match play(rules, &guide, strategy) {
+        Ok(tally) => println!(
+            "{} wins ({} points), {} draws ({} points), {} losses, {} points from moves: {} total",
+            tally.wins,
+            tally.outcome_points.0,
+            tally.draws,
+            tally.outcome_points.1,
+            tally.losses,
+            tally.move_points,
+            tally.total()
+        ),
+        Err(e) => println!("can't play the guide: {}", e),
+    }
+    let (assignment, best) = optimal_strategy(rules, &guide);
+    let reading = rules
+        .my_letters
+        .iter()
+        .zip(assignment)
+        .map(|(letter, mine)| format!("{}={}", letter, rules.names[mine]))
+        .join(" ");
+    println!("best reading {} scores {}", reading, best.total());
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 2);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    let rules: Option<Rules> = args.opt_value_from_str("--rules").unwrap();
+    let strategy: Option<Strategy> = args.opt_value_from_str("--strategy").unwrap();
+    if rules.is_some() || strategy.is_some() {
+        let rules = rules.unwrap_or_else(Rules::classic);
+        report(input, &rules, strategy.unwrap_or(Strategy::Moves));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn score(line: &str, strategy: Strategy) -> u32 {
+        total_score(line, strategy).unwrap()
+    }
+
     #[test]
     fn test_part_one() {
         let input = aoc::read_file("examples", 2);
@@ -112,20 +348,84 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = aoc::read_file("examples", 2);
-        assert_eq!(part_two(&input), Some(100));
+        assert_eq!(part_two(&input), Some(12));
     }
+
     #[test]
     fn test_work_out_score() {
-        assert_eq!(work_out_score(&"A X".to_owned()), 3);
-        assert_eq!(work_out_score(&"B X".to_owned()), 1);
-        assert_eq!(work_out_score(&"C X".to_owned()), 2);
+        assert_eq!(score("A X", Strategy::Outcomes), 3);
+        assert_eq!(score("B X", Strategy::Outcomes), 1);
+        assert_eq!(score("C X", Strategy::Outcomes), 2);
+
+        assert_eq!(score("A Y", Strategy::Outcomes), 1 + 3);
+        assert_eq!(score("B Y", Strategy::Outcomes), 2 + 3);
+        assert_eq!(score("C Y", Strategy::Outcomes), 3 + 3);
+
+        assert_eq!(score("A Z", Strategy::Outcomes), 2 + 6);
+        assert_eq!(score("B Z", Strategy::Outcomes), 3 + 6);
+        assert_eq!(score("C Z", Strategy::Outcomes), 1 + 6);
+    }
+
+    #[test]
+    fn test_lizard_spock() {
+        let rules = Rules::lizard_spock();
+        // Every move beats exactly two others
+        for mine in 0..5 {
+            let wins = (0..5)
+                .filter(|&theirs| rules.outcome(mine, theirs) == Outcome::Win)
+                .count();
+            assert_eq!(wins, 2);
+        }
+        // Spock (E) against lizard (Y), then paper (B) against scissors (Z)
+        let guide = parse_guide(&rules, "E Y\nB Z").unwrap();
+        let tally = play(&rules, &guide, Strategy::Moves).unwrap();
+        assert_eq!((tally.wins, tally.draws, tally.losses), (1, 0, 1));
+        assert_eq!(tally.total(), 4 + 6 + 5);
+        // Aiming to win against rock picks the higher scoring of paper and spock
+        assert_eq!(rules.move_for(0, Outcome::Win), Some(4));
+    }
 
-        assert_eq!(work_out_score(&"A Y".to_owned()), 1 + 3);
-        assert_eq!(work_out_score(&"B Y".to_owned()), 2 + 3);
-        assert_eq!(work_out_score(&"C Y".to_owned()), 3 + 3);
+    #[test]
+    fn test_tally() {
+        let rules = Rules::classic();
+        let guide = parse_guide(&rules, &aoc::read_file("examples", 2)).unwrap();
+        let tally = play(&rules, &guide, Strategy::Moves).unwrap();
+        assert_eq!(
+            tally,
+            Tally {
+                wins: 1,
+                draws: 1,
+                losses: 1,
+                outcome_points: (6, 3),
+                move_points: 6,
+            }
+        );
+    }
 
-        assert_eq!(work_out_score(&"A Z".to_owned()), 2 + 6);
-        assert_eq!(work_out_score(&"B Z".to_owned()), 3 + 6);
-        assert_eq!(work_out_score(&"C Z".to_owned()), 1 + 6);
+    #[test]
+    fn test_optimal_strategy() {
+        let rules = Rules::classic();
+        let guide = parse_guide(&rules, &aoc::read_file("examples", 2)).unwrap();
+        let (assignment, tally) = optimal_strategy(&rules, &guide);
+        // Reading X as scissors, Y as paper and Z as rock wins every round
+        assert_eq!(assignment, vec![2, 1, 0]);
+        assert_eq!(tally.wins, 3);
+        assert_eq!(tally.total(), 24);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let rules = Rules::classic();
+        assert_eq!(
+            parse_guide(&rules, "A Y\nD X"),
+            Err("line 2: \"D\" is not a known move".to_owned())
+        );
+        assert_eq!(
+            parse_guide(&rules, "A Y\n\nAY"),
+            Err("line 3: expected two letters, got \"AY\"".to_owned())
+        );
+        let guide = parse_guide(&rules, "A W").unwrap();
+        assert!(play(&rules, &guide, Strategy::Moves).is_err());
+        assert!(Rules::new(&[("rock", 1)], &[("rock", "rock")], "A", "X").is_err());
     }
 }
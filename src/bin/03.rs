@@ -1,42 +1,144 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::str::FromStr;
 
-fn char_to_num(c: &char) -> u32 {
-    let chars = ('a'..='z')
-        .chain('A'..='Z')
-        .enumerate()
-        .map(|(idx, c)| (c, idx + 1))
-        .collect::<HashMap<char, usize>>();
-    chars[c] as u32
+use itertools::Itertools;
+
+/// Priority of an item: a-z are 1-26 and A-Z are 27-52
+fn priority(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 27),
+        _ => None,
+    }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let total = input
+fn item(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + (priority - 1) as u8) as char,
+        27..=52 => (b'A' + (priority - 27) as u8) as char,
+        _ => panic!("no item has priority {}", priority),
+    }
+}
+
+/// A set of items, with bit n set for the item of priority n
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+struct Items(u64);
+
+impl Items {
+    /// Every item there is, the identity for `intersection`
+    fn all() -> Items {
+        Items(((1 << 52) - 1) << 1)
+    }
+
+    fn intersection(self, other: Items) -> Items {
+        Items(self.0 & other.0)
+    }
+
+    fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    fn priorities(&self) -> impl Iterator<Item = u32> {
+        let bits = self.0;
+        (1..=52).filter(move |p| bits & (1 << p) != 0)
+    }
+
+    fn items(&self) -> impl Iterator<Item = char> {
+        self.priorities().map(item)
+    }
+}
+
+impl FromStr for Items {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().try_fold(Items(0), |items, c| {
+            let p = priority(c).ok_or(format!("\"{}\" is not an item", c))?;
+            Ok(Items(items.0 | 1 << p))
+        })
+    }
+}
+
+fn common(sets: &[Items]) -> Items {
+    sets.iter()
+        .fold(Items::all(), |common, &set| common.intersection(set))
+}
+
+/// The one item shared by every set, an error if there are none or several
+fn only_common(sets: &[Items]) -> Result<char, String> {
+    let shared = common(sets);
+    match shared.len() {
+        1 => Ok(shared.items().next().unwrap()),
+        0 => Err("no item is common".to_owned()),
+        _ => Err(format!(
+            "items {} are all common",
+            shared.items().collect::<String>()
+        )),
+    }
+}
+
+/// Split a rucksack into `count` equally sized compartments
+fn compartments(line: &str, count: usize) -> Result<Vec<Items>, String> {
+    let items = line.chars().collect_vec();
+    if items.is_empty() {
+        return Err("the rucksack is empty".to_owned());
+    }
+    if count == 0 || !items.len().is_multiple_of(count) {
+        return Err(format!(
+            "{} items won't split into {} compartments",
+            items.len(),
+            count
+        ));
+    }
+    items
+        .chunks(items.len() / count)
+        .map(|chunk| chunk.iter().collect::<String>().parse())
+        .collect()
+}
+
+/// Sum the priority of the item common to every compartment of each rucksack
+fn compartment_priorities(input: &str, count: usize) -> Result<u32, String> {
+    input
         .lines()
-        .map(|line| {
-            let size = line.len() / 2;
-            let comp_1: HashSet<char> = HashSet::from_iter(line[0..size].chars());
-            let comp_2: HashSet<char> = HashSet::from_iter(line[size..size * 2].chars());
-            let common_char = comp_1.intersection(&comp_2).take(1).next().unwrap();
-            char_to_num(common_char)
+        .enumerate()
+        .map(|(idx, line)| {
+            compartments(line, count)
+                .and_then(|sets| only_common(&sets))
+                .map(|c| priority(c).unwrap())
+                .map_err(|e| format!("line {}: {}", idx + 1, e))
         })
-        .sum::<u32>();
-    Some(total as u32)
+        .sum()
+}
+
+/// Sum the priority of the badge common to each group of `size` elves
+fn group_priorities(input: &str, size: usize) -> Result<u32, String> {
+    let lines = input.lines().collect_vec();
+    if size == 0 || !lines.len().is_multiple_of(size) {
+        return Err(format!(
+            "{} rucksacks won't split into groups of {}",
+            lines.len(),
+            size
+        ));
+    }
+    lines
+        .chunks(size)
+        .enumerate()
+        .map(|(group, lines)| {
+            let sets = lines
+                .iter()
+                .map(|line| line.parse())
+                .collect::<Result<Vec<Items>, _>>();
+            sets.and_then(|sets| only_common(&sets))
+                .map(|c| priority(c).unwrap())
+                .map_err(|e| format!("group {}: {}", group + 1, e))
+        })
+        .sum()
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    Some(compartment_priorities(input, 2).unwrap())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let mut lines = input.lines().collect::<Vec<&str>>();
-    let mut score: u32 = 0;
-    while lines.len() >= 3 {
-        let line1: HashSet<char> = HashSet::from_iter(lines.pop().unwrap().chars());
-        let line2: HashSet<char> = HashSet::from_iter(lines.pop().unwrap().chars());
-        let line3: HashSet<char> = HashSet::from_iter(lines.pop().unwrap().chars());
-        let common = line1.intersection(&line2);
-        let next_set: HashSet<char> = HashSet::from_iter(common.into_iter().cloned());
-        let common = next_set.intersection(&line3);
-        score = score + char_to_num(common.take(1).next().unwrap())
-    }
-    Some(score)
+    Some(group_priorities(input, 3).unwrap())
 }
 
 fn main() {
@@ -51,10 +153,12 @@ mod tests {
 
     #[test]
     fn test_convert() {
-        assert_eq!(char_to_num(&'a'), 1);
-        assert_eq!(char_to_num(&'z'), 26);
-        assert_eq!(char_to_num(&'A'), 27);
-        assert_eq!(char_to_num(&'Z'), 52);
+        assert_eq!(priority('a'), Some(1));
+        assert_eq!(priority('z'), Some(26));
+        assert_eq!(priority('A'), Some(27));
+        assert_eq!(priority('Z'), Some(52));
+        assert_eq!(priority('!'), None);
+        assert!((1..=52).all(|p| priority(item(p)) == Some(p)));
     }
 
     #[test]
@@ -68,4 +172,60 @@ mod tests {
         let input = aoc::read_file("examples", 3);
         assert_eq!(part_two(&input), Some(70));
     }
+
+    #[test]
+    fn test_common_items() {
+        let sets = ["abcXYZ", "bcdXZ", "cXZz"].map(|s| s.parse::<Items>().unwrap());
+        assert_eq!(common(&sets).items().collect::<String>(), "cXZ");
+        assert_eq!(
+            only_common(&sets),
+            Err("items cXZ are all common".to_owned())
+        );
+        assert_eq!(
+            only_common(&["ab".parse().unwrap(), "cd".parse().unwrap()]),
+            Err("no item is common".to_owned())
+        );
+        assert_eq!(
+            "a-b".parse::<Items>(),
+            Err("\"-\" is not an item".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_compartments() {
+        assert_eq!(compartment_priorities("abcAxyzaabcd", 3), Ok(1));
+        assert_eq!(
+            compartment_priorities("abAcdA\nabcde", 2),
+            Err("line 2: 5 items won't split into 2 compartments".to_owned())
+        );
+        assert_eq!(
+            compartment_priorities("aAbA\n\nab", 2),
+            Err("line 2: the rucksack is empty".to_owned())
+        );
+        assert_eq!(
+            compartment_priorities("aé", 3),
+            Err("line 1: 2 items won't split into 3 compartments".to_owned())
+        );
+        assert_eq!(
+            compartment_priorities("aé", 2),
+            Err("line 1: \"é\" is not an item".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_groups() {
+        let input = aoc::read_file("examples", 3);
+        assert_eq!(
+            group_priorities(&input, 6),
+            Err("group 1: no item is common".to_owned())
+        );
+        assert_eq!(
+            group_priorities(&input, 4),
+            Err("6 rucksacks won't split into groups of 4".to_owned())
+        );
+        assert_eq!(
+            group_priorities(&input, 2),
+            Err("group 1: items frsFM are all common".to_owned())
+        );
+    }
 }
@@ -1,59 +1,201 @@
 use std::ops::RangeInclusive;
 
+use itertools::Itertools;
 use nom::bytes::complete::tag;
 use nom::character::complete::{newline, u32 as nom32};
 use nom::multi::separated_list1;
 use nom::sequence::separated_pair;
 use nom::{self, IResult};
 
+/// The section assignments of one group of elves
+type Group = Vec<RangeInclusive<u32>>;
+
 fn assignment(input: &str) -> IResult<&str, RangeInclusive<u32>> {
     let (input, (start, end)) = separated_pair(nom32, tag("-"), nom32)(input)?;
     Ok((input, start..=end))
 }
 
-fn assignments(input: &str) -> IResult<&str, (RangeInclusive<u32>, RangeInclusive<u32>)> {
-    let (input, (first, second)) = separated_pair(assignment, tag(","), assignment)(input)?;
-    Ok((input, (first, second)))
+fn assignments(input: &str) -> IResult<&str, Group> {
+    separated_list1(tag(","), assignment)(input)
+}
+
+fn all_assignments(input: &str) -> IResult<&str, Vec<Group>> {
+    separated_list1(newline, assignments)(input)
+}
+
+/// A set of sections, held as sorted ranges that neither overlap nor touch
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct IntervalSet {
+    ranges: Vec<RangeInclusive<u32>>,
 }
 
-fn all_assignments(input: &str) -> IResult<&str, Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>> {
-    let (input, all_assignments) = separated_list1(newline, assignments)(input)?;
-    Ok((input, all_assignments))
+impl FromIterator<RangeInclusive<u32>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<u32>>>(iter: I) -> Self {
+        let mut ranges: Vec<RangeInclusive<u32>> = Vec::new();
+        let sorted = iter
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .sorted_by_key(|r| *r.start());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if *range.start() as u64 <= *last.end() as u64 + 1 => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => ranges.push(range),
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+impl IntervalSet {
+    fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let start = *a.start().max(b.start());
+            let end = *a.end().min(b.end());
+            if start <= end {
+                ranges.push(start..=end);
+            }
+            // Move past whichever range finishes first
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    /// Return true if every section of `other` is in this set
+    fn contains_set(&self, other: &IntervalSet) -> bool {
+        self.intersection(other) == *other
+    }
+
+    fn is_disjoint(&self, other: &IntervalSet) -> bool {
+        self.intersection(other).ranges.is_empty()
+    }
+
+    /// Number of sections in the set
+    fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|r| (r.end() - r.start()) as u64 + 1)
+            .sum()
+    }
 }
 
-/// Return true if one range completely contains the other
-fn range_contains(r1: &RangeInclusive<u32>, r2: &RangeInclusive<u32>) -> bool {
-    (r1.start() <= r2.start() && r1.end() >= r2.end())
-        || (r2.start() <= r1.start() && r2.end() >= r1.end())
+impl From<RangeInclusive<u32>> for IntervalSet {
+    fn from(range: RangeInclusive<u32>) -> Self {
+        std::iter::once(range).collect()
+    }
 }
 
-/// Return true if one range overlaps the other
-fn range_overlap(r1: &RangeInclusive<u32>, r2: &RangeInclusive<u32>) -> bool {
-    !(r1.start() > r2.end() || r2.start() > r1.end())
+/// Every section assigned to at least one elf in the group
+fn coverage(group: &[RangeInclusive<u32>]) -> IntervalSet {
+    group.iter().cloned().collect()
+}
+
+/// Sections assigned to more than `k` elves in the group
+fn covered_more_than(group: &[RangeInclusive<u32>], k: usize) -> IntervalSet {
+    // Sweep over the points where the number of elves changes
+    let events = group
+        .iter()
+        .filter(|r| !r.is_empty())
+        .flat_map(|r| [(*r.start() as u64, 1), (*r.end() as u64 + 1, -1)])
+        .sorted()
+        .collect_vec();
+    let mut count: i64 = 0;
+    let mut ranges = Vec::new();
+    for (&(at, change), next) in events.iter().zip(events.iter().skip(1)) {
+        count += change;
+        if count > k as i64 && next.0 > at {
+            ranges.push(at as u32..=(next.0 - 1) as u32);
+        }
+    }
+    ranges.into_iter().collect()
+}
+
+/// Elves whose every section is also assigned to another elf in the group
+fn redundant(group: &[RangeInclusive<u32>]) -> Vec<usize> {
+    (0..group.len())
+        .filter(|&elf| {
+            let others = group
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != elf)
+                .map(|(_, r)| r.clone())
+                .collect::<IntervalSet>();
+            others.contains_set(&group[elf].clone().into())
+        })
+        .collect()
+}
+
+/// Pairs of elves in the group that share at least one section
+fn overlapping_pairs(group: &[RangeInclusive<u32>]) -> Vec<(usize, usize)> {
+    (0..group.len())
+        .tuple_combinations()
+        .filter(|&(a, b)| {
+            let (a, b): (IntervalSet, IntervalSet) =
+                (group[a].clone().into(), group[b].clone().into());
+            !a.is_disjoint(&b)
+        })
+        .collect()
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let (_, lines) = all_assignments(input).unwrap();
-    let result = lines
-        .into_iter()
-        .filter(|(r1, r2)| range_contains(r1, r2))
+    let (_, groups) = all_assignments(input).unwrap();
+    let result = groups
+        .iter()
+        .filter(|group| !redundant(group).is_empty())
         .count();
     Some(result as u32)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let (_, lines) = all_assignments(input).unwrap();
-    let result = lines
-        .into_iter()
-        .filter(|(r1, r2)| range_overlap(r1, r2))
+    let (_, groups) = all_assignments(input).unwrap();
+    let result = groups
+        .iter()
+        .filter(|group| !overlapping_pairs(group).is_empty())
         .count();
     Some(result as u32)
 }
 
+/// Summarise every group, and the sections assigned across all of them
+fn report(input: &str, k: usize) {
+    let (_, groups) = all_assignments(input).unwrap();
+    let mut total = IntervalSet::default();
+    for (idx, group) in groups.iter().enumerate() {
+        let covered = coverage(group);
+        println!(
+            "line {}: {} sections covered, {} by more than {} elves, redundant elves {:?}, overlapping pairs {:?}",
+            idx + 1,
+            covered.len(),
+            covered_more_than(group, k).len(),
+            k,
+            redundant(group),
+            overlapping_pairs(group)
+        );
+        total = total.union(&covered);
+    }
+    println!("{} sections assigned in total", total.len());
+}
+
 fn main() {
     let input = &aoc::read_file("inputs", 4);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    if let Some(k) = args.opt_value_from_str("--report").unwrap() {
+        report(input, k);
+    }
 }
 
 #[cfg(test)]
@@ -71,4 +213,42 @@ mod tests {
         let input = aoc::read_file("examples", 4);
         assert_eq!(part_two(&input), Some(4));
     }
+
+    #[test]
+    fn test_interval_set() {
+        let set: IntervalSet = [5..=7, 1..=2, 3..=3, 10..=12, 11..=11]
+            .into_iter()
+            .collect();
+        assert_eq!(set.ranges, vec![1..=3, 5..=7, 10..=12]);
+        assert_eq!(set.len(), 9);
+        let other: IntervalSet = [2..=5, 12..=20].into_iter().collect();
+        assert_eq!(set.intersection(&other).ranges, vec![2..=3, 5..=5, 12..=12]);
+        assert_eq!(set.union(&other).ranges, vec![1..=7, 10..=20]);
+        assert!(set.contains_set(&(10..=11).into()));
+        assert!(!set.contains_set(&(3..=5).into()));
+        assert!(set.is_disjoint(&(8..=9).into()));
+    }
+
+    #[test]
+    fn test_many_elves() {
+        let (_, groups) = all_assignments("2-4,3-8,6-6,1-2\n1-1,4-4").unwrap();
+        let group = &groups[0];
+        assert_eq!(coverage(group).ranges, vec![1..=8]);
+        assert_eq!(coverage(group).len(), 8);
+        assert_eq!(covered_more_than(group, 1).ranges, vec![2..=4, 6..=6]);
+        assert_eq!(covered_more_than(group, 2).ranges, vec![]);
+        // 2-4 is covered by 1-2 and 3-8 between them
+        assert_eq!(redundant(group), vec![0, 2]);
+        assert_eq!(overlapping_pairs(group), vec![(0, 1), (0, 3), (1, 2)]);
+        assert_eq!(overlapping_pairs(&groups[1]), vec![]);
+    }
+
+    #[test]
+    fn test_redundant_together() {
+        // The middle elf is covered by the other two between them
+        let group = vec![1..=4, 3..=6, 5..=9];
+        assert_eq!(redundant(&group), vec![1]);
+        // Identical assignments make each other redundant
+        assert_eq!(redundant(&[2..=3, 2..=3]), vec![0, 1]);
+    }
 }
@@ -1,109 +1,467 @@
-pub fn part_one(input: &str) -> Option<String> {
-    let mut stacks: Vec<Vec<char>> = Vec::new();
-    let mut sections = input.split("\n\n");
-    let mut crates: Vec<&str> = sections.next().unwrap().lines().collect();
-    let instructions: Vec<&str> = sections.next().unwrap().lines().collect();
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::Range;
 
-    for _ in 0..9 {
-        stacks.push(Vec::new());
-    }
+use aoc::helpers::visualize::Visualizer;
+use array2d::Array2D;
+use itertools::Itertools;
+use nom::bytes::complete::{is_not, tag};
+use nom::character::complete::{alphanumeric1, char, space0, u32 as nom32};
+use nom::combinator::{all_consuming, consumed};
+use nom::multi::many0;
+use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::IResult;
+
+/// Stacks of crates, bottom crate first, along with the label of each stack
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CrateYard {
+    labels: Vec<String>,
+    stacks: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Move {
+    count: usize,
+    from: usize,
+    to: usize,
+}
 
-    // Take off the line of stack numbers as we don't need it
-    crates.pop();
+/// Parse tokens spread across a line, keeping the columns each one spans
+fn positioned<'a, O>(
+    line: &'a str,
+    token: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> IResult<&'a str, Vec<(Range<usize>, O)>> {
+    let (rest, tokens) =
+        all_consuming(terminated(many0(preceded(space0, consumed(token))), space0))(line)?;
+    let tokens = tokens
+        .into_iter()
+        .map(|(text, out)| {
+            let start = text.as_ptr() as usize - line.as_ptr() as usize;
+            (start..start + text.len(), out)
+        })
+        .collect();
+    Ok((rest, tokens))
+}
+
+fn crate_label(input: &str) -> IResult<&str, &str> {
+    delimited(char('['), is_not("] "), char(']'))(input)
+}
 
-    for line in crates {
-        let chars: Vec<char> = line.chars().collect();
-        let crates_on_line = (chars.len() - 3) / 4 + 1;
-        for idx in 0..crates_on_line {
-            let c = chars[1 + idx * 4];
-            if c != ' ' {
-                stacks[idx].insert(0, c);
+fn move_line(input: &str) -> IResult<&str, (u32, &str, &str)> {
+    let (input, (_, count, _, from, _, to)) = all_consuming(tuple((
+        tag("move "),
+        nom32,
+        tag(" from "),
+        alphanumeric1,
+        tag(" to "),
+        alphanumeric1,
+    )))(input)?;
+    Ok((input, (count, from, to)))
+}
+
+impl CrateYard {
+    /// Parse the drawing of the stacks, the last line of which labels them.
+    /// Crates belong to whichever stack label they sit above.
+    fn parse(drawing: &str) -> Result<CrateYard, String> {
+        let lines = drawing.lines().collect::<Vec<_>>();
+        let (label_line, rows) = lines.split_last().ok_or("there's no drawing")?;
+        let (_, labels) = positioned(label_line, alphanumeric1)
+            .map_err(|_| format!("line {}: expected stack labels", lines.len()))?;
+        let mut yard = CrateYard {
+            labels: labels.iter().map(|(_, l)| l.to_string()).collect(),
+            stacks: vec![Vec::new(); labels.len()],
+        };
+        // Stack the crates from the bottom row up
+        for (row_idx, row) in rows.iter().enumerate().rev() {
+            let line_no = row_idx + 1;
+            let (_, crates) = positioned(row, crate_label)
+                .map_err(|_| format!("line {}: expected crates like [A]", line_no))?;
+            for (span, label) in crates {
+                let stack = labels
+                    .iter()
+                    .position(|(at, _)| span.start < at.end && at.start < span.end)
+                    .ok_or(format!("line {}: [{}] isn't above a stack", line_no, label))?;
+                if yard.stacks[stack].len() != rows.len() - 1 - row_idx {
+                    return Err(format!(
+                        "line {}: [{}] is floating above stack {}",
+                        line_no, label, yard.labels[stack]
+                    ));
+                }
+                yard.stacks[stack].push(label.to_owned());
             }
         }
+        Ok(yard)
     }
 
-    // Now we can read and process the moves
-    for instruction in instructions {
-        let words = instruction.split(" ").collect::<Vec<&str>>();
-        let count = words[1].parse::<usize>().unwrap();
-        let source = words[3].parse::<usize>().unwrap();
-        let dest = words[5].parse::<usize>().unwrap();
+    fn stack_index(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|l| l == label)
+    }
 
-        // Process instruction
-        for _ in 0..count {
-            let c = stacks[source - 1].pop().unwrap();
-            stacks[dest - 1].push(c);
+    /// The crate on top of each stack, skipping empty stacks
+    fn tops(&self) -> Vec<String> {
+        self.stacks
+            .iter()
+            .filter_map(|s| s.last())
+            .cloned()
+            .collect()
+    }
+
+    fn apply(&mut self, crane: &impl Crane, mv: &Move) -> Result<(), String> {
+        if mv.from == mv.to {
+            return Err(format!(
+                "can't move crates from stack {} onto itself",
+                self.labels[mv.from]
+            ));
+        }
+        let height = self.stacks[mv.from].len();
+        if mv.count > height {
+            return Err(format!(
+                "can't move {} crates from stack {} which has {}",
+                mv.count, self.labels[mv.from], height
+            ));
         }
+        let mut lifted = self.stacks[mv.from].split_off(height - mv.count);
+        crane.arrange(&mut lifted);
+        self.stacks[mv.to].extend(lifted);
+        Ok(())
     }
-    let mut result: Vec<char> = Vec::new();
-    for mut s in stacks {
-        let c = s.pop().unwrap_or(' ');
-        if c != ' ' {
-            result.push(c);
+
+    /// Put back the crates `mv` moved, as if it had never happened
+    fn undo(&mut self, crane: &impl Crane, mv: &Move) -> Result<(), String> {
+        if mv.from == mv.to {
+            return Err(format!(
+                "can't move crates from stack {} onto itself",
+                self.labels[mv.from]
+            ));
+        }
+        let height = self.stacks[mv.to].len();
+        if mv.count > height {
+            return Err(format!(
+                "can't take {} crates back from stack {} which has {}",
+                mv.count, self.labels[mv.to], height
+            ));
         }
+        let mut placed = self.stacks[mv.to].split_off(height - mv.count);
+        crane.unarrange(&mut placed);
+        self.stacks[mv.from].extend(placed);
+        Ok(())
     }
+}
 
-    Some(result.into_iter().collect())
+/// Draws the yard in the same format it's read in
+impl Display for CrateYard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .stacks
+            .iter()
+            .flatten()
+            .map(|c| c.len() + 2)
+            .chain(self.labels.iter().map(|l| l.len() + 2))
+            .max()
+            .unwrap_or(3);
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let cells = self
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("{:^width$}", format!("[{}]", c)),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        let labels = self
+            .labels
+            .iter()
+            .map(|l| format!("{:^width$}", l))
+            .collect::<Vec<_>>();
+        write!(f, "{}", labels.join(" "))
+    }
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let mut stacks: Vec<Vec<char>> = Vec::new();
-    let mut sections = input.split("\n\n");
-    let mut crates: Vec<&str> = sections.next().unwrap().lines().collect();
-    let instructions: Vec<&str> = sections.next().unwrap().lines().collect();
+/// A crane moves crates between stacks, how it puts down what it lifted
+/// depends on the model
+trait Crane {
+    /// Reorder crates lifted off a stack (top crate last) into the order
+    /// they're put down on the new stack
+    fn arrange<T>(&self, lifted: &mut [T]);
 
-    for _ in 0..9 {
-        stacks.push(Vec::new());
+    /// Reverse `arrange`, by default assuming it's its own inverse
+    fn unarrange<T>(&self, placed: &mut [T]) {
+        self.arrange(placed);
     }
+}
 
-    // Take off the line of stack numbers as we don't need it
-    crates.pop();
+/// Moves one crate at a time, so a lifted pile ends up upside down
+struct CrateMover9000;
 
-    for line in crates {
-        let chars: Vec<char> = line.chars().collect();
-        let crates_on_line = (chars.len() - 3) / 4 + 1;
-        for idx in 0..crates_on_line {
-            let c = chars[1 + idx * 4];
-            if c != ' ' {
-                stacks[idx].insert(0, c);
-            }
+/// Moves every crate at once, keeping their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9000 {
+    fn arrange<T>(&self, lifted: &mut [T]) {
+        lifted.reverse();
+    }
+}
+
+impl Crane for CrateMover9001 {
+    fn arrange<T>(&self, _lifted: &mut [T]) {}
+}
+
+fn parse_input(input: &str) -> Result<(CrateYard, Vec<Move>), String> {
+    let (drawing, moves) = input
+        .split_once("\n\n")
+        .ok_or("expected a drawing and moves separated by a blank line")?;
+    let yard = CrateYard::parse(drawing)?;
+    let first_move_line = drawing.lines().count() + 2;
+    let moves = moves
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            let line_no = first_move_line + idx;
+            let (_, (count, from, to)) = move_line(line)
+                .map_err(|_| format!("line {}: expected a move, got \"{}\"", line_no, line))?;
+            let stack = |label| {
+                yard.stack_index(label)
+                    .ok_or(format!("line {}: there's no stack {}", line_no, label))
+            };
+            Ok(Move {
+                count: count as usize,
+                from: stack(from)?,
+                to: stack(to)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok((yard, moves))
+}
+
+/// Steps through a rearrangement one move at a time, in either direction
+struct Replay<'a, C: Crane> {
+    yard: CrateYard,
+    moves: &'a [Move],
+    crane: &'a C,
+    position: usize,
+}
+
+impl<'a, C: Crane> Replay<'a, C> {
+    fn new(yard: CrateYard, moves: &'a [Move], crane: &'a C) -> Self {
+        Replay {
+            yard,
+            moves,
+            crane,
+            position: 0,
         }
     }
 
-    // Now we can read and process the moves
-    for instruction in instructions {
-        let words = instruction.split(" ").collect::<Vec<&str>>();
-        let count = words[1].parse::<usize>().unwrap();
-        let source = words[3].parse::<usize>().unwrap();
-        let dest = words[5].parse::<usize>().unwrap();
+    /// The yard after the moves made so far
+    fn yard(&self) -> &CrateYard {
+        &self.yard
+    }
 
-        // Process instruction
-        let mut tmp: Vec<char> = Vec::new();
-        for _ in 0..count {
-            let c = stacks[source - 1].pop().unwrap();
-            tmp.push(c);
+    /// Make the next move, returning false once there are none left
+    fn step(&mut self) -> Result<bool, String> {
+        let Some(mv) = self.moves.get(self.position) else {
+            return Ok(false);
+        };
+        self.yard
+            .apply(self.crane, mv)
+            .map_err(|e| format!("move {}: {}", self.position + 1, e))?;
+        self.position += 1;
+        Ok(true)
+    }
+
+    /// Undo the last move made, returning false when back at the start
+    fn step_back(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        let mv = &self.moves[self.position];
+        // Moves that were made can always be undone
+        self.yard.undo(self.crane, mv).unwrap();
+        true
+    }
+
+    /// Every state from here to the end, starting with the current one
+    fn history(mut self) -> Result<Vec<CrateYard>, String> {
+        let mut states = vec![self.yard.clone()];
+        while self.step()? {
+            states.push(self.yard.clone());
         }
-        for _ in 0..count {
-            stacks[dest - 1].push(tmp.pop().unwrap());
+        Ok(states)
+    }
+}
+
+fn rearrange(input: &str, crane: &impl Crane) -> Result<String, String> {
+    let (yard, moves) = parse_input(input)?;
+    let mut replay = Replay::new(yard, &moves, crane);
+    while replay.step()? {}
+    Ok(replay.yard().tops().concat())
+}
+
+/// Work out the smallest starting yards that `moves` rearrange so `tops`
+/// ends up on top. Only the crates that finish on top can be known, the
+/// rest are shown as `?`. There's more than one answer when `tops` needs
+/// crates left untouched on stacks the moves otherwise empty.
+fn reconstruct(
+    labels: &[String],
+    moves: &[Move],
+    crane: &impl Crane,
+    tops: &[String],
+) -> Result<Vec<CrateYard>, String> {
+    // Follow crates by id, adding crates to the bottom of a stack whenever
+    // a move needs more than it holds. Adding them underneath doesn't change
+    // what earlier moves took off the top.
+    let mut initial: Vec<Vec<usize>> = vec![Vec::new(); labels.len()];
+    let mut stacks: Vec<Vec<usize>> = vec![Vec::new(); labels.len()];
+    let mut next_id = 0;
+    for (idx, mv) in moves.iter().enumerate() {
+        if mv.from == mv.to {
+            return Err(format!(
+                "move {}: can't move crates from stack {} onto itself",
+                idx + 1,
+                labels[mv.from]
+            ));
+        }
+        let missing = mv.count.saturating_sub(stacks[mv.from].len());
+        let new_ids = next_id..next_id + missing;
+        next_id += missing;
+        initial[mv.from].splice(0..0, new_ids.clone());
+        stacks[mv.from].splice(0..0, new_ids);
+        let height = stacks[mv.from].len();
+        let mut lifted = stacks[mv.from].split_off(height - mv.count);
+        crane.arrange(&mut lifted);
+        stacks[mv.to].extend(lifted);
+    }
+    let (filled, empty): (Vec<usize>, Vec<usize>) =
+        (0..labels.len()).partition(|&s| !stacks[s].is_empty());
+    if tops.len() < filled.len() || tops.len() > labels.len() {
+        return Err(format!(
+            "the moves leave {} to {} stacks with crates, not {}",
+            filled.len(),
+            labels.len(),
+            tops.len()
+        ));
+    }
+    let yards = empty
+        .into_iter()
+        .combinations(tops.len() - filled.len())
+        .map(|untouched| {
+            let top_stacks = filled.iter().chain(&untouched).copied().sorted();
+            let mut known = HashMap::new();
+            let mut yard = CrateYard {
+                labels: labels.to_vec(),
+                stacks: vec![Vec::new(); labels.len()],
+            };
+            for (stack, label) in top_stacks.zip(tops) {
+                match stacks[stack].last() {
+                    Some(&id) => {
+                        known.insert(id, label);
+                    }
+                    None => yard.stacks[stack].push(label.clone()),
+                }
+            }
+            for (stack, ids) in initial.iter().enumerate() {
+                yard.stacks[stack].extend(ids.iter().map(|id| match known.get(id) {
+                    Some(&label) => label.clone(),
+                    None => "?".to_owned(),
+                }));
+            }
+            yard
+        })
+        .collect();
+    Ok(yards)
+}
+
+fn to_grid(yard: &CrateYard, height: usize, width: usize) -> Array2D<char> {
+    let text = yard.to_string();
+    let lines = text.lines().collect_vec();
+    let mut grid = Array2D::filled_with(' ', height, width);
+    // Line the drawings up along the bottom, where the labels are
+    for (row, line) in lines.iter().rev().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            grid[(height - 1 - row, col)] = c;
         }
     }
-    let mut result: Vec<char> = Vec::new();
-    for mut s in stacks {
-        let c = s.pop().unwrap_or(' ');
-        if c != ' ' {
-            result.push(c);
+    grid
+}
+
+fn visualize(input: &str, visualizer: &mut Visualizer) {
+    let (yard, moves) = parse_input(input).unwrap();
+    let history = Replay::new(yard, &moves, &CrateMover9000)
+        .history()
+        .unwrap();
+    let drawings = history.iter().map(|yard| yard.to_string()).collect_vec();
+    let height = drawings.iter().map(|d| d.lines().count()).max().unwrap();
+    let width = drawings
+        .iter()
+        .flat_map(|d| d.lines().map(|l| l.len()))
+        .max()
+        .unwrap();
+    for yard in &history {
+        visualizer.frame(&to_grid(yard, height, width));
+    }
+    // Then wind it back to the start, which loops nicely as a gif
+    let mut replay = Replay::new(history[0].clone(), &moves, &CrateMover9000);
+    while replay.step().unwrap() {}
+    while replay.step_back() {
+        visualizer.frame(&to_grid(replay.yard(), height, width));
+    }
+}
+
+/// Read the crates on top from the command line, separated by commas or,
+/// if every label is a single character, run together like an answer
+fn parse_tops(tops: &str) -> Vec<String> {
+    if tops.contains(',') {
+        tops.split(',')
+            .map(|label| label.trim().to_owned())
+            .collect()
+    } else {
+        tops.chars().map(String::from).collect()
+    }
+}
+
+/// Print every smallest starting yard that ends with `tops` on top
+fn print_reconstructions(input: &str, tops: &[String], crane: u32) {
+    let (yard, moves) = parse_input(input).unwrap();
+    let yards = match crane {
+        9000 => reconstruct(&yard.labels, &moves, &CrateMover9000, tops),
+        9001 => reconstruct(&yard.labels, &moves, &CrateMover9001, tops),
+        _ => Err(format!("there's no CrateMover {}", crane)),
+    };
+    match yards {
+        Ok(yards) => {
+            for yard in yards {
+                println!("{}\n", yard);
+            }
         }
+        Err(e) => println!("can't reconstruct the yard: {}", e),
     }
+}
 
-    Some(result.into_iter().collect())
+pub fn part_one(input: &str) -> Option<String> {
+    Some(rearrange(input, &CrateMover9000).unwrap())
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    Some(rearrange(input, &CrateMover9001).unwrap())
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 5);
-    println!("Part A solution: {}", part_one(input).unwrap());
-    println!("Part B solution: {}", part_two(input).unwrap());
-    //aoc::solve!(1, part_one, input);
-    //aoc::solve!(2, part_two, input);
+    aoc::solve!(1, part_one, input);
+    aoc::solve!(2, part_two, input);
+    if let Some(mut visualizer) = Visualizer::from_args() {
+        visualize(input, &mut visualizer);
+    }
+
+    let mut args = pico_args::Arguments::from_env();
+    let tops: Option<String> = args.opt_value_from_str("--reconstruct").unwrap();
+    if let Some(tops) = tops {
+        let crane = args.opt_value_from_str("--crane").unwrap().unwrap_or(9000);
+        print_reconstructions(input, &parse_tops(&tops), crane);
+    }
 }
 
 #[cfg(test)]
@@ -121,4 +479,149 @@ mod tests {
         let input = aoc::read_file("examples", 5);
         assert_eq!(part_two(&input), Some("MCD".to_owned()));
     }
+
+    #[test]
+    fn test_round_trip() {
+        let input = aoc::read_file("examples", 5);
+        let (yard, moves) = parse_input(&input).unwrap();
+        assert_eq!(
+            yard.stacks,
+            vec![vec!["Z", "N"], vec!["M", "C", "D"], vec!["P"]]
+        );
+        assert_eq!(
+            moves[1],
+            Move {
+                count: 3,
+                from: 0,
+                to: 2
+            }
+        );
+        let (drawing, _) = input.split_once("\n\n").unwrap();
+        assert_eq!(yard.to_string(), drawing);
+    }
+
+    #[test]
+    fn test_many_stacks() {
+        // Ten stacks with crates labelled by more than one letter
+        let drawing = [
+            "                                                  [AB]     ",
+            "[AA]                                              [AC] [XY]",
+            "[ZZ] [BB] [CC] [DD] [EE] [FF] [GG] [HH] [II] [JJ] [KK] [LL]",
+            " 1    2    3    4    5    6    7    8    9    10   11   12 ",
+        ]
+        .join("\n");
+        let mut yard = CrateYard::parse(&drawing).unwrap();
+        assert_eq!(yard.labels.len(), 12);
+        assert_eq!(yard.stacks[10], vec!["KK", "AC", "AB"]);
+        assert_eq!(yard.to_string(), drawing);
+        let mv = Move {
+            count: 2,
+            from: 10,
+            to: 0,
+        };
+        yard.apply(&CrateMover9001, &mv).unwrap();
+        assert_eq!(
+            yard.tops(),
+            vec!["AB", "BB", "CC", "DD", "EE", "FF", "GG", "HH", "II", "JJ", "KK", "XY"]
+        );
+    }
+
+    #[test]
+    fn test_illegal_moves() {
+        let input = aoc::read_file("examples", 5).replace("move 3 from 1", "move 4 from 1");
+        assert_eq!(
+            rearrange(&input, &CrateMover9000),
+            Err("move 2: can't move 4 crates from stack 1 which has 3".to_owned())
+        );
+        let input = aoc::read_file("examples", 5).replace("to 3", "to 4");
+        assert_eq!(
+            rearrange(&input, &CrateMover9000),
+            Err("line 7: there's no stack 4".to_owned())
+        );
+        let input = aoc::read_file("examples", 5).replace("from 2 to 1", "from 2 to 2");
+        assert_eq!(
+            rearrange(&input, &CrateMover9000),
+            Err("move 1: can't move crates from stack 2 onto itself".to_owned())
+        );
+        assert_eq!(
+            CrateYard::parse("[A]    \n    [B]\n 1   2 "),
+            Err("line 1: [A] is floating above stack 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let input = aoc::read_file("examples", 5);
+        let (yard, moves) = parse_input(&input).unwrap();
+        let mut replay = Replay::new(yard.clone(), &moves, &CrateMover9001);
+        assert!(!replay.step_back());
+        while replay.step().unwrap() {}
+        assert_eq!(replay.yard().tops(), vec!["M", "C", "D"]);
+        // Stepping all the way back recovers the starting yard
+        replay.step_back();
+        assert_eq!(replay.yard().tops(), vec!["C", "D"]);
+        while replay.step_back() {}
+        assert_eq!(replay.yard(), &yard);
+
+        let history = Replay::new(yard, &moves, &CrateMover9000)
+            .history()
+            .unwrap();
+        assert_eq!(
+            history.iter().map(|y| y.tops().concat()).collect_vec(),
+            vec!["NDP", "DCP", "CZ", "MZ", "CMZ"]
+        );
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let input = aoc::read_file("examples", 5);
+        let (yard, moves) = parse_input(&input).unwrap();
+        let yards = reconstruct(&yard.labels, &moves, &CrateMover9000, &parse_tops("CMZ")).unwrap();
+        // Five crates are enough, with Z at the bottom of stack 1 as in the example
+        assert_eq!(yards.len(), 1);
+        assert_eq!(
+            yards[0].to_string(),
+            ["    [?]    ", "[?] [C]    ", "[Z] [M]    ", " 1   2   3 "].join("\n")
+        );
+        let mut replay = Replay::new(yards[0].clone(), &moves, &CrateMover9000);
+        while replay.step().unwrap() {}
+        assert_eq!(replay.yard().tops().concat(), "CMZ");
+
+        let yards = reconstruct(&yard.labels, &moves, &CrateMover9001, &parse_tops("MCD")).unwrap();
+        let mut replay = Replay::new(yards[0].clone(), &moves, &CrateMover9001);
+        while replay.step().unwrap() {}
+        assert_eq!(replay.yard().tops().concat(), "MCD");
+
+        // The move empties stack 1 and stack 3 is never touched, so crates
+        // left alone under either would show up on top too
+        let moves = vec![Move {
+            count: 1,
+            from: 0,
+            to: 1,
+        }];
+        let yards = reconstruct(&yard.labels, &moves, &CrateMover9000, &parse_tops("ABC")).unwrap();
+        assert_eq!(yards.len(), 1);
+        assert_eq!(yards[0].stacks, vec![vec!["A", "B"], vec![], vec!["C"]]);
+        let yards = reconstruct(&yard.labels, &moves, &CrateMover9000, &parse_tops("AB")).unwrap();
+        assert_eq!(yards.len(), 2);
+        assert!(reconstruct(&yard.labels, &moves, &CrateMover9000, &parse_tops("")).is_err());
+        let moves = vec![Move {
+            count: 2,
+            from: 1,
+            to: 1,
+        }];
+        assert_eq!(
+            reconstruct(&yard.labels, &moves, &CrateMover9000, &parse_tops("AB")),
+            Err("move 1: can't move crates from stack 2 onto itself".to_owned())
+        );
+
+        // Labels longer than one character need separating
+        let tops = parse_tops("XY, M,Z");
+        assert_eq!(tops, vec!["XY", "M", "Z"]);
+        let moves = parse_input(&input).unwrap().1;
+        let yards = reconstruct(&yard.labels, &moves, &CrateMover9000, &tops).unwrap();
+        let mut replay = Replay::new(yards[0].clone(), &moves, &CrateMover9000);
+        while replay.step().unwrap() {}
+        assert_eq!(replay.yard().tops(), tops);
+    }
 }
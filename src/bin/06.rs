@@ -1,13 +1,67 @@
-use itertools::Itertools;
+use std::collections::VecDeque;
+use std::io::{BufReader, Read};
 
-fn solve_with_window(input: &str, window_length: usize) -> Option<u32> {
-    let (idx, _) = input
-        .as_bytes()
-        .windows(window_length)
+/// Finds markers, runs of `window` distinct bytes, in a stream. It keeps a
+/// count of each byte in the window along with how many bytes are in there
+/// more than once, so each new byte is handled in constant time.
+struct MarkerDetector {
+    window: usize,
+    recent: VecDeque<u8>,
+    counts: [usize; 256],
+    repeated: usize,
+}
+
+impl MarkerDetector {
+    fn new(window: usize) -> Self {
+        assert!(window > 0, "markers must be at least one byte long");
+        MarkerDetector {
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            counts: [0; 256],
+            repeated: 0,
+        }
+    }
+
+    /// Add the next byte, returning true if it ends a marker
+    fn push(&mut self, byte: u8) -> bool {
+        self.recent.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+        if self.recent.len() > self.window {
+            let old = self.recent.pop_front().unwrap();
+            self.counts[old as usize] -= 1;
+            if self.counts[old as usize] == 1 {
+                self.repeated -= 1;
+            }
+        }
+        self.recent.len() == self.window && self.repeated == 0
+    }
+}
+
+/// Positions of every marker in a stream, counted as the number of bytes
+/// read up to the end of the marker
+fn markers<R: Read>(reader: R, window: usize) -> impl Iterator<Item = Result<usize, String>> {
+    let mut detector = MarkerDetector::new(window);
+    BufReader::new(reader)
+        .bytes()
         .enumerate()
-        .find(|(_, window)| window.iter().all_unique())
-        .unwrap();
-    Some((idx + window_length) as u32)
+        .filter_map(move |(idx, byte)| match byte {
+            Ok(byte) => detector.push(byte).then_some(Ok(idx + 1)),
+            Err(e) => Some(Err(format!("byte {}: {}", idx + 1, e))),
+        })
+}
+
+/// The first marker in a stream, `None` if there isn't one
+fn first_marker<R: Read>(reader: R, window: usize) -> Result<Option<usize>, String> {
+    markers(reader, window).next().transpose()
+}
+
+fn solve_with_window(input: &str, window_length: usize) -> Option<u32> {
+    first_marker(input.as_bytes(), window_length)
+        .unwrap()
+        .map(|idx| idx as u32)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
@@ -27,6 +81,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::repeat;
 
     #[test]
     fn test_part_one() {
@@ -44,4 +99,28 @@ mod tests {
         assert_eq!(part_two("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Some(29));
         assert_eq!(part_two("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Some(26));
     }
+
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(part_one("abcabcabc"), None);
+        assert_eq!(part_two("abcd"), None);
+        assert_eq!(first_marker("".as_bytes(), 1), Ok(None));
+    }
+
+    #[test]
+    fn test_all_markers() {
+        let found = markers("abcabcdd".as_bytes(), 3).collect::<Result<Vec<_>, _>>();
+        assert_eq!(found, Ok(vec![3, 4, 5, 6, 7]));
+        let found = markers("aab".as_bytes(), 1).collect::<Result<Vec<_>, _>>();
+        assert_eq!(found, Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_stream() {
+        // A million repeated bytes before the marker, never held in memory
+        let signal = repeat(b'a')
+            .take(1_000_000)
+            .chain("bcdefghijklmn".as_bytes());
+        assert_eq!(first_marker(signal, 14), Ok(Some(1_000_013)));
+    }
 }
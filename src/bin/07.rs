@@ -1,103 +1,503 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::path::Path;
 
+use aoc::helpers::memo::Memo;
 use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, not_line_ending, u64 as nom64};
+use nom::combinator::{all_consuming, map, value};
+use nom::sequence::{preceded, separated_pair};
+use nom::IResult;
 
-fn update_tree(tree: &mut HashMap<String, usize>, path: &Vec<String>, size: usize) {
-    // Update the tree with the file provided
-    let mut cwd = path.clone();
-    while cwd.len() > 0 {
-        let path_string = cwd.join("");
-        let current_val = tree.get(&path_string);
-        match current_val {
-            Some(_) => {
-                tree.get_mut(&path_string).map(|old| *old += size);
+/// One line of the terminal transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line<'a> {
+    Cd(&'a str),
+    Ls,
+    Dir(&'a str),
+    File(u64, &'a str),
+}
+
+fn parse_line(input: &str) -> IResult<&str, Line<'_>> {
+    all_consuming(alt((
+        map(preceded(tag("$ cd "), not_line_ending), Line::Cd),
+        value(Line::Ls, tag("$ ls")),
+        map(preceded(tag("dir "), not_line_ending), Line::Dir),
+        map(
+            separated_pair(nom64, char(' '), not_line_ending),
+            |(size, name)| Line::File(size, name),
+        ),
+    )))(input)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Dir(BTreeMap<String, usize>),
+    File(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+    name: String,
+    parent: Option<usize>,
+    kind: Kind,
+}
+
+/// A directory tree, with nodes held in a `Vec` and referred to by index.
+/// The root directory is always node 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FsTree {
+    nodes: Vec<Node>,
+}
+
+const ROOT: usize = 0;
+
+impl FsTree {
+    fn new() -> Self {
+        FsTree {
+            nodes: vec![Node {
+                name: "/".to_owned(),
+                parent: None,
+                kind: Kind::Dir(BTreeMap::new()),
+            }],
+        }
+    }
+
+    /// Build the tree a terminal session explored. Listing the same
+    /// directory more than once doesn't add anything twice.
+    fn from_transcript(input: &str) -> Result<FsTree, String> {
+        let mut tree = FsTree::new();
+        let mut cwd = ROOT;
+        for (idx, text) in input.lines().enumerate() {
+            let error = |e: String| format!("line {}: {}", idx + 1, e);
+            let (_, line) =
+                parse_line(text).map_err(|_| error(format!("can't understand \"{}\"", text)))?;
+            match line {
+                Line::Cd("/") => cwd = ROOT,
+                Line::Cd("..") => cwd = tree.nodes[cwd].parent.unwrap_or(ROOT),
+                Line::Cd(name) => cwd = tree.mkdir(cwd, name).map_err(error)?,
+                Line::Ls => {}
+                Line::Dir(name) => {
+                    tree.mkdir(cwd, name).map_err(error)?;
+                }
+                Line::File(size, name) => {
+                    tree.add_file(cwd, name, size).map_err(error)?;
+                }
             }
-            _ => {
-                tree.insert(path_string, size);
+        }
+        Ok(tree)
+    }
+
+    fn children(&self, dir: usize) -> Option<&BTreeMap<String, usize>> {
+        match &self.nodes[dir].kind {
+            Kind::Dir(children) => Some(children),
+            Kind::File(_) => None,
+        }
+    }
+
+    fn child(&self, dir: usize, name: &str) -> Option<usize> {
+        self.children(dir)?.get(name).copied()
+    }
+
+    fn add_node(&mut self, dir: usize, name: &str, kind: Kind) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_owned(),
+            parent: Some(dir),
+            kind,
+        });
+        match &mut self.nodes[dir].kind {
+            Kind::Dir(children) => children.insert(name.to_owned(), idx),
+            Kind::File(_) => panic!("{} isn't a directory", self.path(dir)),
+        };
+        idx
+    }
+
+    /// Return the directory `name` inside `dir`, creating it if need be
+    fn mkdir(&mut self, dir: usize, name: &str) -> Result<usize, String> {
+        match self.child(dir, name) {
+            Some(idx) if self.children(idx).is_some() => Ok(idx),
+            Some(idx) => Err(format!("{} is a file", self.path(idx))),
+            None => Ok(self.add_node(dir, name, Kind::Dir(BTreeMap::new()))),
+        }
+    }
+
+    /// Add a file to `dir`, a file listed again just takes the latest size
+    fn add_file(&mut self, dir: usize, name: &str, size: u64) -> Result<usize, String> {
+        match self.child(dir, name) {
+            Some(idx) => match &mut self.nodes[idx].kind {
+                Kind::File(old) => {
+                    *old = size;
+                    Ok(idx)
+                }
+                Kind::Dir(_) => Err(format!("{} is a directory", self.path(idx))),
+            },
+            None => Ok(self.add_node(dir, name, Kind::File(size))),
+        }
+    }
+
+    /// Full path of a node, directories end with a `/`
+    fn path(&self, idx: usize) -> String {
+        self.path_via(idx, &mut |parent| self.path(parent))
+    }
+
+    /// Full path of a node, getting its parent's path from `parent_path`
+    fn path_via(&self, idx: usize, parent_path: &mut dyn FnMut(usize) -> String) -> String {
+        let node = &self.nodes[idx];
+        let name = match node.kind {
+            Kind::Dir(_) if idx != ROOT => format!("{}/", node.name),
+            _ => node.name.clone(),
+        };
+        match node.parent {
+            Some(parent) => parent_path(parent) + &name,
+            None => name,
+        }
+    }
+
+    /// Cache for building the paths of many nodes, so directories they
+    /// share are only spelled out once
+    fn paths(&self) -> impl FnMut(usize) -> String + '_ {
+        let mut memo = Memo::new("paths");
+        move |idx| memo.call(idx, &|path, idx| self.path_via(idx, path))
+    }
+
+    /// Find a node from a path like `/a/e/i`
+    fn lookup(&self, path: &str) -> Option<usize> {
+        path.strip_prefix('/')?
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(ROOT, |dir, name| self.child(dir, name))
+    }
+
+    /// Size of every node, a directory's size is everything under it
+    fn sizes(&self) -> Vec<u64> {
+        let mut sizes = vec![0; self.nodes.len()];
+        // Children are always added after their parents, so working
+        // backwards sees every child before its parent
+        for idx in (0..self.nodes.len()).rev() {
+            if let Kind::File(size) = self.nodes[idx].kind {
+                sizes[idx] = size;
+            }
+            if let Some(parent) = self.nodes[idx].parent {
+                sizes[parent] += sizes[idx];
             }
         }
-        cwd.pop();
+        sizes
+    }
+
+    /// Size of every directory, the root's first
+    fn dir_sizes(&self) -> Vec<u64> {
+        let sizes = self.sizes();
+        (0..self.nodes.len())
+            .filter(|&idx| self.children(idx).is_some())
+            .map(|idx| sizes[idx])
+            .collect()
+    }
+
+    fn size_of(&self, path: &str) -> Option<u64> {
+        Some(self.sizes()[self.lookup(path)?])
     }
-}
 
-fn dirs_from_input(input: &str) -> HashMap<String, usize> {
-    let mut cwd: Vec<String> = Vec::new();
-    let mut dirs: HashMap<String, usize> = HashMap::new();
-    let lines: Vec<&str> = input.lines().collect();
-
-    for line in lines {
-        let words: Vec<&str> = line.split(" ").collect();
-        if words[0] == "$" {
-            // Command
-            match words[1] {
-                "cd" => {
-                    let dest = words[2];
-                    match dest {
-                        ".." => {
-                            cwd.pop();
-                        }
-                        "/" => {
-                            cwd.clear();
-                            cwd.push(dest.to_owned());
-                        }
-                        _ => {
-                            let mut new_dir = dest.to_owned();
-                            new_dir.push_str("/");
-                            cwd.push(new_dir);
-                        }
-                    }
+    /// The `n` biggest files, biggest first
+    fn largest_files(&self, n: usize) -> Vec<(String, u64)> {
+        let mut path = self.paths();
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node)| match node.kind {
+                Kind::File(size) => Some((path(idx), size)),
+                Kind::Dir(_) => None,
+            })
+            .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)))
+            .take(n)
+            .collect()
+    }
+
+    /// Directories whose size passes `keep`, smallest first
+    fn dirs_where(&self, keep: impl Fn(u64) -> bool) -> Vec<(String, u64)> {
+        let sizes = self.sizes();
+        let mut path = self.paths();
+        (0..self.nodes.len())
+            .filter(|&idx| self.children(idx).is_some() && keep(sizes[idx]))
+            .map(|idx| (path(idx), sizes[idx]))
+            .sorted_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)))
+            .collect()
+    }
+
+    fn dirs_at_most(&self, threshold: u64) -> Vec<(String, u64)> {
+        self.dirs_where(|size| size <= threshold)
+    }
+
+    fn dirs_at_least(&self, threshold: u64) -> Vec<(String, u64)> {
+        self.dirs_where(|size| size >= threshold)
+    }
+
+    /// Read a real directory on disk into a tree, without following links
+    fn from_disk(path: &Path) -> std::io::Result<FsTree> {
+        fn walk(tree: &mut FsTree, dir: usize, path: &Path) -> std::io::Result<()> {
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let metadata = entry.path().symlink_metadata()?;
+                if metadata.is_dir() {
+                    let child = tree.mkdir(dir, &name).unwrap();
+                    walk(tree, child, &entry.path())?;
+                } else if metadata.is_file() {
+                    tree.add_file(dir, &name, metadata.len()).unwrap();
                 }
-                _ => {}
             }
-        } else {
-            match words[1] {
-                "dir" => {}
-                _ => {
-                    let first = words[0];
-                    let second = words[1];
-                    match first {
-                        "dir" => {}
-                        _ => {
-                            let size = first.parse::<usize>().unwrap();
-                            let _ = second.to_owned();
-                            update_tree(&mut dirs, &cwd, size);
-                        }
-                    }
+            Ok(())
+        }
+        let mut tree = FsTree::new();
+        walk(&mut tree, ROOT, path)?;
+        Ok(tree)
+    }
+
+    /// A terminal session that explores the whole tree, listing every
+    /// directory once
+    fn transcript(&self) -> String {
+        fn explore(tree: &FsTree, dir: usize, lines: &mut Vec<String>) {
+            let children = tree.children(dir).unwrap();
+            lines.push("$ ls".to_owned());
+            for &child in children.values() {
+                lines.push(match tree.nodes[child].kind {
+                    Kind::Dir(_) => format!("dir {}", tree.nodes[child].name),
+                    Kind::File(size) => format!("{} {}", size, tree.nodes[child].name),
+                });
+            }
+            for &child in children.values() {
+                if tree.children(child).is_some() {
+                    lines.push(format!("$ cd {}", tree.nodes[child].name));
+                    explore(tree, child, lines);
+                    lines.push("$ cd ..".to_owned());
                 }
             }
         }
+        let mut lines = vec!["$ cd /".to_owned()];
+        explore(self, ROOT, &mut lines);
+        lines.join("\n")
+    }
+
+    fn render(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        idx: usize,
+        sizes: &[u64],
+        prefix: &str,
+    ) -> std::fmt::Result {
+        let children = self.children(idx).unwrap();
+        for (pos, &child) in children.values().enumerate() {
+            let last = pos == children.len() - 1;
+            let node = &self.nodes[child];
+            let suffix = if self.children(child).is_some() {
+                "/"
+            } else {
+                ""
+            };
+            let branch = if last { "└── " } else { "├── " };
+            writeln!(
+                f,
+                "{}{}{}{} ({})",
+                prefix, branch, node.name, suffix, sizes[child]
+            )?;
+            if self.children(child).is_some() {
+                let indent = if last { "    " } else { "│   " };
+                self.render(f, child, sizes, &format!("{}{}", prefix, indent))?;
+            }
+        }
+        Ok(())
     }
-    dirs
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let dirs = dirs_from_input(input);
-    let max_size: usize = 100000;
-    let total: usize = dirs.values().filter(|v| v <= &&max_size).sum();
-    Some(total as u32)
+/// Draws the tree like the `tree` command, with the size of every node
+impl Display for FsTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sizes = self.sizes();
+        writeln!(f, "/ ({})", sizes[ROOT])?;
+        self.render(f, ROOT, &sizes, "")
+    }
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let dirs = dirs_from_input(input);
-    let current_size = dirs.get("/").unwrap();
-    let max_size = 70000000;
-    let needed_space = 30000000;
-    let current_space = max_size - current_size;
-    let need_to_free = needed_space - current_space;
-
-    let size_to_free = dirs
-        .values()
-        .sorted()
-        .find(|v| v > &&need_to_free)
-        .unwrap()
-        .clone();
-    Some(size_to_free as u32)
+/// Names can't be empty or hold a `/`, and only `cd` understands `/` and `..`
+fn has_valid_name(line: &Line) -> bool {
+    match *line {
+        Line::Ls | Line::Cd("/") | Line::Cd("..") => true,
+        Line::Cd(name) | Line::Dir(name) | Line::File(_, name) => {
+            !name.is_empty() && !name.contains('/') && name != "." && name != ".."
+        }
+    }
+}
+
+/// Check a transcript could have come from a real session, returning an
+/// error for each line where it couldn't have
+fn validate(input: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut tree = FsTree::new();
+    let mut cwd = ROOT;
+    // Everything listed in each directory along with the line of the `ls`,
+    // and the listing being read now
+    let mut listed: HashMap<usize, (usize, BTreeMap<String, Option<u64>>)> = HashMap::new();
+    let mut listing: Option<(usize, usize, BTreeMap<String, Option<u64>>)> = None;
+
+    let mut finish_listing = |listing: Option<(usize, usize, BTreeMap<_, _>)>,
+                              tree: &FsTree,
+                              errors: &mut Vec<String>| {
+        let Some((dir, line_no, entries)) = listing else {
+            return;
+        };
+        match listed.get(&dir) {
+            Some((first, previous)) if *previous != entries => errors.push(format!(
+                "line {}: listing of {} differs from the one on line {}",
+                line_no,
+                tree.path(dir),
+                first
+            )),
+            Some(_) => {}
+            None => {
+                listed.insert(dir, (line_no, entries));
+            }
+        }
+    };
+
+    for (idx, text) in input.lines().enumerate() {
+        let line_no = idx + 1;
+        let Ok((_, line)) = parse_line(text) else {
+            errors.push(format!("line {}: can't understand \"{}\"", line_no, text));
+            continue;
+        };
+        if !has_valid_name(&line) {
+            errors.push(format!(
+                "line {}: \"{}\" has an invalid name",
+                line_no, text
+            ));
+            continue;
+        }
+        if !matches!(line, Line::Dir(_) | Line::File(..)) {
+            finish_listing(listing.take(), &tree, &mut errors);
+        }
+        match line {
+            Line::Cd("/") => cwd = ROOT,
+            Line::Cd("..") => match tree.nodes[cwd].parent {
+                Some(parent) => cwd = parent,
+                None => errors.push(format!("line {}: cd .. above /", line_no)),
+            },
+            Line::Cd(name) => match tree.child(cwd, name) {
+                Some(child) if tree.children(child).is_some() => cwd = child,
+                Some(child) => errors.push(format!(
+                    "line {}: can't cd into {} which is a file",
+                    line_no,
+                    tree.path(child)
+                )),
+                None => {
+                    errors.push(format!(
+                        "line {}: cd into {} which {} hasn't listed",
+                        line_no,
+                        name,
+                        tree.path(cwd)
+                    ));
+                    cwd = tree.mkdir(cwd, name).unwrap();
+                }
+            },
+            Line::Ls => listing = Some((cwd, line_no, BTreeMap::new())),
+            Line::Dir(_) | Line::File(..) => {
+                let Some((_, _, entries)) = listing.as_mut() else {
+                    errors.push(format!("line {}: listing without an ls", line_no));
+                    continue;
+                };
+                let (name, size, added) = match line {
+                    Line::File(size, name) => (name, Some(size), tree.add_file(cwd, name, size)),
+                    Line::Dir(name) => (name, None, tree.mkdir(cwd, name)),
+                    _ => unreachable!(),
+                };
+                if let Err(e) = added {
+                    errors.push(format!("line {}: {}", line_no, e));
+                }
+                if entries.insert(name.to_owned(), size).is_some() {
+                    errors.push(format!("line {}: {} is listed twice", line_no, name));
+                }
+            }
+        }
+    }
+    finish_listing(listing.take(), &tree, &mut errors);
+    errors
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    let tree = FsTree::from_transcript(input).unwrap();
+    let total = tree
+        .dir_sizes()
+        .into_iter()
+        .filter(|&size| size <= 100000)
+        .sum();
+    Some(total)
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let tree = FsTree::from_transcript(input).unwrap();
+    let disk_size: u64 = 70000000;
+    let needed_space: u64 = 30000000;
+    let sizes = tree.dir_sizes();
+    let used = sizes[0];
+    let free = disk_size.saturating_sub(used);
+    let need_to_free = needed_space.saturating_sub(free);
+    if need_to_free == 0 {
+        // There's already enough space without deleting anything
+        return Some(0);
+    }
+    sizes.into_iter().filter(|&size| size >= need_to_free).min()
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    // Make a transcript from a real directory with `--generate <dir>`
+    let generate: Option<std::path::PathBuf> = args.opt_value_from_str("--generate").unwrap();
+    if let Some(dir) = generate {
+        let tree = FsTree::from_disk(&dir).unwrap();
+        println!("{}", tree.transcript());
+        return;
+    }
+
     let input = &aoc::read_file("inputs", 7);
+    if args.contains("--validate") {
+        for error in validate(input) {
+            println!("{}", error);
+        }
+    }
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+
+    // Explore the tree with `--tree`, `--size <path>` or `--largest <n>`, and
+    // list directories by size with `--at-most <size>` or `--at-least <size>`
+    let show_tree = args.contains("--tree");
+    let size_of: Option<String> = args.opt_value_from_str("--size").unwrap();
+    let largest: Option<usize> = args.opt_value_from_str("--largest").unwrap();
+    let at_most: Option<u64> = args.opt_value_from_str("--at-most").unwrap();
+    let at_least: Option<u64> = args.opt_value_from_str("--at-least").unwrap();
+    let tree = FsTree::from_transcript(input).unwrap();
+    if show_tree {
+        print!("{}", tree);
+    }
+    if let Some(path) = size_of {
+        match tree.size_of(&path) {
+            Some(size) => println!("{} is {}", path, size),
+            None => println!("{} doesn't exist", path),
+        }
+    }
+    for (path, size) in tree.largest_files(largest.unwrap_or(0)) {
+        println!("{} {}", size, path);
+    }
+    if let Some(threshold) = at_most {
+        for (path, size) in tree.dirs_at_most(threshold) {
+            println!("{} {}", size, path);
+        }
+    }
+    if let Some(threshold) = at_least {
+        for (path, size) in tree.dirs_at_least(threshold) {
+            println!("{} {}", size, path);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -115,4 +515,145 @@ mod tests {
         let input = aoc::read_file("examples", 7);
         assert_eq!(part_two(&input), Some(24933642));
     }
+
+    #[test]
+    fn test_queries() {
+        let tree = FsTree::from_transcript(&aoc::read_file("examples", 7)).unwrap();
+        assert_eq!(tree.size_of("/"), Some(48381165));
+        assert_eq!(tree.size_of("/a/e"), Some(584));
+        assert_eq!(tree.size_of("/a/e/i"), Some(584));
+        assert_eq!(tree.size_of("/a/x"), None);
+        assert_eq!(
+            tree.largest_files(2),
+            vec![
+                ("/b.txt".to_owned(), 14848514),
+                ("/c.dat".to_owned(), 8504156)
+            ]
+        );
+        assert_eq!(
+            tree.dirs_at_most(100000),
+            vec![("/a/e/".to_owned(), 584), ("/a/".to_owned(), 94853)]
+        );
+        assert_eq!(
+            tree.dirs_at_least(24000000),
+            vec![("/d/".to_owned(), 24933642), ("/".to_owned(), 48381165)]
+        );
+    }
+
+    #[test]
+    fn test_repeated_ls() {
+        let input = aoc::read_file("examples", 7);
+        let twice = format!(
+            "{}\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ ls\n29116 f",
+            input
+        );
+        assert_eq!(
+            FsTree::from_transcript(&twice),
+            FsTree::from_transcript(&input)
+        );
+        assert_eq!(part_one(&twice), Some(95437));
+    }
+
+    #[test]
+    fn test_enough_space() {
+        assert_eq!(part_two("$ cd /\n$ ls\n100 a"), Some(0));
+        // Only deleting everything frees enough
+        assert_eq!(part_two("$ cd /\n$ ls\n100000000 a"), Some(100000000));
+    }
+
+    #[test]
+    fn test_bad_lines() {
+        assert_eq!(
+            FsTree::from_transcript("$ cd /\n$ rm -rf a"),
+            Err("line 2: can't understand \"$ rm -rf a\"".to_owned())
+        );
+        assert_eq!(
+            FsTree::from_transcript("$ ls\n10 a\n$ cd a"),
+            Err("line 3: /a is a file".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let tree = FsTree::from_transcript(&aoc::read_file("examples", 7)).unwrap();
+        let expected = "\
+/ (48381165)
+├── a/ (94853)
+│   ├── e/ (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d/ (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)
+";
+        assert_eq!(tree.to_string(), expected);
+    }
+
+    #[test]
+    fn test_validate() {
+        let input = aoc::read_file("examples", 7);
+        assert_eq!(validate(&input), Vec::<String>::new());
+        let bad = "\
+$ cd /
+$ ls
+dir a
+10 b
+$ cd x
+$ cd ..
+$ cd ..
+12 c
+$ ls
+dir a
+11 b
+$ cd b
+$ oops
+dir a/b";
+        assert_eq!(
+            validate(bad),
+            vec![
+                "line 5: cd into x which / hasn't listed",
+                "line 7: cd .. above /",
+                "line 8: listing without an ls",
+                "line 9: listing of / differs from the one on line 2",
+                "line 12: can't cd into /b which is a file",
+                "line 13: can't understand \"$ oops\"",
+                "line 14: \"dir a/b\" has an invalid name",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let tree = FsTree::from_transcript(&aoc::read_file("examples", 7)).unwrap();
+        let transcript = tree.transcript();
+        assert_eq!(validate(&transcript), Vec::<String>::new());
+        let again = FsTree::from_transcript(&transcript).unwrap();
+        assert_eq!(again.to_string(), tree.to_string());
+        assert_eq!(part_one(&transcript), Some(95437));
+    }
+
+    #[test]
+    fn test_from_disk() {
+        let dir = std::env::temp_dir().join(format!("aoc_fs_tree_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("a/e")).unwrap();
+        std::fs::create_dir_all(dir.join("d")).unwrap();
+        std::fs::write(dir.join("a/e/i"), vec![0; 584]).unwrap();
+        std::fs::write(dir.join("b.txt"), "hello").unwrap();
+        let tree = FsTree::from_disk(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(tree.size_of("/"), Some(589));
+        assert_eq!(tree.size_of("/d"), Some(0));
+        let transcript = tree.transcript();
+        assert_eq!(validate(&transcript), Vec::<String>::new());
+        assert_eq!(
+            FsTree::from_transcript(&transcript).unwrap().to_string(),
+            tree.to_string()
+        );
+    }
 }
@@ -1,123 +1,203 @@
-fn visible_in_matrix(matrix: Vec<Vec<u32>>, viz: &mut Vec<Vec<u32>>) {
-    let (rows, cols) = (matrix[0].len(), matrix.len());
-
-    for (row_idx, row) in matrix.iter().enumerate() {
-        let mut current_highest: i32 = -1;
-        for (col_idx, cell) in row.iter().enumerate() {
-            if *cell as i32 > current_highest {
-                viz[row_idx][col_idx] = 1;
-                current_highest = *cell as i32;
-            }
-        }
+use std::str::FromStr;
 
-        let mut current_highest: i32 = -1;
-        for (col_idx, cell) in row.iter().enumerate().rev() {
-            if *cell as i32 > current_highest {
-                viz[row_idx][col_idx] = 1;
-                current_highest = *cell as i32;
-            }
+use array2d::Array2D;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// How many trees can be seen from a tree in each direction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Views {
+    up: usize,
+    down: usize,
+    left: usize,
+    right: usize,
+}
+
+impl Views {
+    fn get_mut(&mut self, direction: Direction) -> &mut usize {
+        match direction {
+            Direction::Up => &mut self.up,
+            Direction::Down => &mut self.down,
+            Direction::Left => &mut self.left,
+            Direction::Right => &mut self.right,
         }
     }
-    // Do cols
-    for col_idx in 0..cols {
-        let mut current_highest: i32 = -1;
-        for row_idx in 0..rows {
-            let val = matrix[row_idx][col_idx];
-            if val as i32 > current_highest {
-                viz[row_idx][col_idx] = 1;
-                current_highest = val as i32;
-            }
+
+    fn scenic_score(&self) -> u64 {
+        [self.up, self.down, self.left, self.right]
+            .iter()
+            .map(|&d| d as u64)
+            .product()
+    }
+}
+
+#[derive(Debug)]
+struct Forest {
+    heights: Array2D<u8>,
+}
+
+impl FromStr for Forest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| {
+                line.chars()
+                    .map(|c| {
+                        c.to_digit(10).map(|d| d as u8).ok_or(format!(
+                            "line {}: \"{}\" isn't a height",
+                            idx + 1,
+                            c
+                        ))
+                    })
+                    .collect::<Result<Vec<u8>, String>>()
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if rows.is_empty() || rows[0].is_empty() {
+            return Err("there are no trees".to_owned());
         }
-        let mut current_highest: i32 = -1;
-        for row_idx in (0..rows).rev() {
-            let val = matrix[row_idx][col_idx];
-            if val as i32 > current_highest {
-                viz[row_idx][col_idx] = 1;
-                current_highest = val as i32;
-            }
+        if let Some(idx) = rows.iter().position(|row| row.len() != rows[0].len()) {
+            return Err(format!(
+                "line {}: has {} trees but line 1 has {}",
+                idx + 1,
+                rows[idx].len(),
+                rows[0].len()
+            ));
         }
+        Ok(Forest {
+            heights: Array2D::from_rows(&rows).unwrap(),
+        })
     }
 }
 
-fn probe(map: &Vec<Vec<u32>>, row: usize, col: usize) -> u32 {
-    let (rows, cols) = (map[0].len(), map.len());
-    let height_at_poi = map[row][col];
-    // Look left
-    let mut total_left = 0;
-    for row_idx in (0..row).rev() {
-        total_left += 1;
-        if map[row_idx][col] >= height_at_poi {
-            break;
+impl Forest {
+    /// Every line of trees, as (row, column) positions ordered so that
+    /// looking in `direction` from a tree looks back towards the start
+    fn lines(&self, direction: Direction) -> Vec<Vec<(usize, usize)>> {
+        let (rows, cols) = (self.heights.num_rows(), self.heights.num_columns());
+        match direction {
+            Direction::Up => (0..cols)
+                .map(|c| (0..rows).map(|r| (r, c)).collect())
+                .collect(),
+            Direction::Down => (0..cols)
+                .map(|c| (0..rows).rev().map(|r| (r, c)).collect())
+                .collect(),
+            Direction::Left => (0..rows)
+                .map(|r| (0..cols).map(|c| (r, c)).collect())
+                .collect(),
+            Direction::Right => (0..rows)
+                .map(|r| (0..cols).rev().map(|c| (r, c)).collect())
+                .collect(),
         }
     }
-    // Look right
-    let mut total_right = 0;
-    for row_idx in row + 1..rows {
-        total_right += 1;
-        if map[row_idx][col] >= height_at_poi {
-            break;
+
+    /// For every tree, how far it can see in `direction` and whether it can
+    /// be seen from that edge. A stack of the trees that might still block
+    /// the view, tallest at the bottom, means each tree is looked at a
+    /// constant number of times.
+    fn look(&self, direction: Direction) -> Array2D<(usize, bool)> {
+        let (rows, cols) = (self.heights.num_rows(), self.heights.num_columns());
+        let mut result = Array2D::filled_with((0, false), rows, cols);
+        for line in self.lines(direction) {
+            let mut blockers: Vec<usize> = Vec::new();
+            for (pos, &at) in line.iter().enumerate() {
+                let height = self.heights[at];
+                // Shorter trees can't block the view from here or further on
+                while let Some(&top) = blockers.last() {
+                    if self.heights[line[top]] >= height {
+                        break;
+                    }
+                    blockers.pop();
+                }
+                result[at] = match blockers.last() {
+                    Some(&blocker) => (pos - blocker, false),
+                    None => (pos, true),
+                };
+                blockers.push(pos);
+            }
         }
+        result
     }
-    // Look up
-    let mut total_up = 0;
-    for col_idx in (0..col).rev() {
-        total_up += 1;
-        if map[row][col_idx] >= height_at_poi {
-            break;
+
+    /// Whether each tree can be seen from outside the forest
+    fn visibility(&self) -> Array2D<bool> {
+        let (rows, cols) = (self.heights.num_rows(), self.heights.num_columns());
+        let mut visible = Array2D::filled_with(false, rows, cols);
+        for direction in DIRECTIONS {
+            let looked = self.look(direction);
+            for (at, &(_, seen)) in looked.enumerate_row_major() {
+                visible[at] |= seen;
+            }
         }
+        visible
     }
-    // Look down
-    let mut total_down = 0;
-    for col_idx in col + 1..cols {
-        total_down += 1;
-        if map[row][col_idx] >= height_at_poi {
-            break;
+
+    /// Viewing distance in each direction from every tree
+    fn views(&self) -> Array2D<Views> {
+        let (rows, cols) = (self.heights.num_rows(), self.heights.num_columns());
+        let mut views = Array2D::filled_with(Views::default(), rows, cols);
+        for direction in DIRECTIONS {
+            let looked = self.look(direction);
+            for (at, &(distance, _)) in looked.enumerate_row_major() {
+                *views[at].get_mut(direction) = distance;
+            }
         }
+        views
     }
-    total_up * total_left * total_down * total_right
-}
 
-fn input_to_matrix(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|l| {
-            l.split("")
-                .filter(|v| !v.is_empty())
-                .map(|v| v.parse::<u32>().unwrap())
-                .collect()
-        })
-        .collect()
-}
-pub fn part_one(input: &str) -> Option<u32> {
-    let matrix = input_to_matrix(input);
-    let mut viz: Vec<Vec<u32>> = matrix
-        .iter()
-        .map(|row| row.iter().map(|_| 0).collect())
-        .collect();
-
-    visible_in_matrix(matrix, &mut viz);
+    fn scenic_scores(&self) -> Array2D<u64> {
+        let views = self.views();
+        let scores = views
+            .elements_row_major_iter()
+            .map(|v| v.scenic_score())
+            .collect::<Vec<_>>();
+        Array2D::from_row_major(&scores, views.num_rows(), views.num_columns()).unwrap()
+    }
 
-    let total = viz.iter().map(|row| row.iter().sum::<u32>()).sum();
+    /// The (row, column) with the best scenic score, the first one if
+    /// there's a tie, along with how far it can see
+    fn best_location(&self) -> ((usize, usize), Views) {
+        let scores = self.scenic_scores();
+        let mut best = (0, 0);
+        for (at, &score) in scores.enumerate_row_major() {
+            if score > scores[best] {
+                best = at;
+            }
+        }
+        (best, self.views()[best])
+    }
+}
 
-    Some(total)
+pub fn part_one(input: &str) -> Option<u32> {
+    let forest = input.parse::<Forest>().unwrap();
+    let total = forest
+        .visibility()
+        .elements_row_major_iter()
+        .filter(|&&v| v)
+        .count();
+    Some(total as u32)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let matrix = input_to_matrix(input);
-    let (rows, cols) = (matrix[0].len(), matrix.len());
-    let los_map: Vec<Vec<u32>> = (0..rows)
-        .map(|row_idx| {
-            (0..cols)
-                .map(|col_idx| probe(&matrix, row_idx as usize, col_idx as usize))
-                .collect()
-        })
-        .collect();
-    let result = los_map
-        .iter()
-        .map(|row| row.iter().max().unwrap())
-        .max()
-        .unwrap();
-    Some(*result)
+pub fn part_two(input: &str) -> Option<u64> {
+    let forest = input.parse::<Forest>().unwrap();
+    let ((row, col), views) = forest.best_location();
+    aoc::debug!("best tree is at row {}, column {}: {:?}", row, col, views);
+    Some(views.scenic_score())
 }
 
 fn main() {
@@ -141,4 +221,92 @@ mod tests {
         let input = aoc::read_file("examples", 8);
         assert_eq!(part_two(&input), Some(8));
     }
+
+    #[test]
+    fn test_best_location() {
+        let forest = aoc::read_file("examples", 8).parse::<Forest>().unwrap();
+        assert_eq!(
+            forest.best_location(),
+            (
+                (3, 2),
+                Views {
+                    up: 2,
+                    down: 1,
+                    left: 2,
+                    right: 2
+                }
+            )
+        );
+        assert_eq!(forest.scenic_scores()[(1, 2)], 4);
+        assert_eq!(forest.scenic_scores()[(0, 0)], 0);
+    }
+
+    // Count viewing distances the slow way, one tree at a time
+    fn brute_force_views(heights: &Array2D<u8>, (row, col): (usize, usize)) -> Views {
+        let height = heights[(row, col)];
+        let distance = |trees: Vec<u8>| {
+            trees
+                .iter()
+                .position(|&h| h >= height)
+                .map_or(trees.len(), |p| p + 1)
+        };
+        let column = heights
+            .column_iter(col)
+            .unwrap()
+            .copied()
+            .collect::<Vec<_>>();
+        let line = heights.row_iter(row).unwrap().copied().collect::<Vec<_>>();
+        Views {
+            up: distance(column[..row].iter().rev().copied().collect()),
+            down: distance(column[row + 1..].to_vec()),
+            left: distance(line[..col].iter().rev().copied().collect()),
+            right: distance(line[col + 1..].to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_non_square() {
+        // A wide and a tall forest of pseudo-random heights
+        let mut seed: u32 = 12345;
+        for (rows, cols) in [(4, 11), (13, 3)] {
+            let text = (0..rows)
+                .map(|_| {
+                    (0..cols)
+                        .map(|_| {
+                            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                            char::from_digit((seed >> 16) % 10, 10).unwrap()
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let forest = text.parse::<Forest>().unwrap();
+            let views = forest.views();
+            let visible = forest.visibility();
+            for row in 0..rows {
+                for col in 0..cols {
+                    let expected = brute_force_views(&forest.heights, (row, col));
+                    assert_eq!(views[(row, col)], expected);
+                    let height = forest.heights[(row, col)];
+                    let seen = (0..row).all(|r| forest.heights[(r, col)] < height)
+                        || (row + 1..rows).all(|r| forest.heights[(r, col)] < height)
+                        || (0..col).all(|c| forest.heights[(row, c)] < height)
+                        || (col + 1..cols).all(|c| forest.heights[(row, c)] < height);
+                    assert_eq!(visible[(row, col)], seen);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_forest() {
+        assert_eq!(
+            "123\n12".parse::<Forest>().err(),
+            Some("line 2: has 2 trees but line 1 has 3".to_owned())
+        );
+        assert_eq!(
+            "123\n1x3".parse::<Forest>().err(),
+            Some("line 2: \"x\" isn't a height".to_owned())
+        );
+    }
 }
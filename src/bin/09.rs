@@ -1,22 +1,22 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::ops::{Add, Sub};
+use std::str::FromStr;
 
-use nom::character::complete::{space0, u32 as nom32};
+use aoc::helpers::visualize::{to_text, Pixel, Visualizer};
+use array2d::Array2D;
+use nom::character::complete::{alpha1, space1, u32 as nom32};
+use nom::combinator::all_consuming;
 use nom::sequence::separated_pair;
-use nom::{character::complete::alpha1, IResult};
+use nom::IResult;
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
 struct Posn {
     x: i32,
     y: i32,
 }
 
-impl Default for Posn {
-    fn default() -> Self {
-        Self { x: 0, y: 0 }
-    }
-}
-
 impl Posn {
     fn signum(self) -> Self {
         Self {
@@ -24,11 +24,10 @@ impl Posn {
             y: self.y.signum(),
         }
     }
-    fn abs(self) -> Self {
-        Self {
-            x: self.x.abs(),
-            y: self.y.abs(),
-        }
+
+    /// Number of king's moves between two positions
+    fn distance(self, other: Self) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
     }
 }
 impl Add for Posn {
@@ -52,96 +51,412 @@ impl Sub for Posn {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "U" => Ok(Direction::Up),
+            "D" => Ok(Direction::Down),
+            "L" => Ok(Direction::Left),
+            "R" => Ok(Direction::Right),
+            "UL" => Ok(Direction::UpLeft),
+            "UR" => Ok(Direction::UpRight),
+            "DL" => Ok(Direction::DownLeft),
+            "DR" => Ok(Direction::DownRight),
+            _ => Err(format!("unknown direction \"{}\"", s)),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letters = match self {
+            Direction::Up => "U",
+            Direction::Down => "D",
+            Direction::Left => "L",
+            Direction::Right => "R",
+            Direction::UpLeft => "UL",
+            Direction::UpRight => "UR",
+            Direction::DownLeft => "DL",
+            Direction::DownRight => "DR",
+        };
+        write!(f, "{}", letters)
+    }
 }
-fn parse_direction(input: &str) -> IResult<&str, Direction> {
-    let (input, word) = alpha1(input)?;
-    match word {
-        "U" => Ok((input, Direction::Up)),
-        "D" => Ok((input, Direction::Down)),
-        "L" => Ok((input, Direction::Left)),
-        "R" => Ok((input, Direction::Right)),
-        _ => Ok((input, Direction::Right)),
+
+impl Direction {
+    /// How far the head moves in one step
+    fn delta(self) -> Posn {
+        let (x, y) = match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        };
+        Posn { x, y }
     }
 }
 
-fn parse_move(input: &str) -> IResult<&str, (Direction, u32)> {
-    separated_pair(parse_direction, space0, nom32)(input)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move {
+    direction: Direction,
+    count: u32,
 }
 
-// Given head and tail positions, return new tail position
-fn move_tail(head: Posn, tail: Posn) -> Posn {
-    let diff = tail - head;
-    let unit = diff.signum();
-    let abs = diff.abs();
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.direction, self.count)
+    }
+}
+
+fn parse_move(input: &str) -> IResult<&str, (&str, u32)> {
+    all_consuming(separated_pair(alpha1, space1, nom32))(input)
+}
+
+fn parse_moves(input: &str) -> Result<Vec<Move>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            let (_, (word, count)) = parse_move(line.trim_end())
+                .map_err(|_| format!("line {}: can't understand \"{}\"", idx + 1, line))?;
+            let direction = word
+                .parse::<Direction>()
+                .map_err(|e| format!("line {}: {}", idx + 1, e))?;
+            Ok(Move { direction, count })
+        })
+        .collect()
+}
+
+/// Where a knot ends up once `lead` has moved. It steps towards the
+/// knot it follows until it is no more than `slack` away.
+fn follow(lead: Posn, knot: Posn, slack: i32) -> Posn {
+    let mut knot = knot;
+    while lead.distance(knot) > slack {
+        knot = knot + (lead - knot).signum();
+    }
+    knot
+}
+
+/// A rope of knots that all start at the origin, each trailing the one
+/// before it, and how many times each knot has arrived at each position
+#[derive(Debug, Clone)]
+struct Rope {
+    knots: Vec<Posn>,
+    slack: i32,
+    visits: Vec<HashMap<Posn, usize>>,
+}
+
+impl Rope {
+    fn new(knots: usize) -> Self {
+        Rope::with_slack(knots, 1)
+    }
+
+    fn with_slack(knots: usize, slack: i32) -> Self {
+        assert!(knots > 0, "a rope needs at least one knot");
+        assert!(
+            slack > 0,
+            "knots can't share a position with the one they follow"
+        );
+        Rope {
+            knots: vec![Posn::default(); knots],
+            slack,
+            visits: vec![HashMap::from([(Posn::default(), 1)]); knots],
+        }
+    }
+
+    /// Move the head by `delta` and let the rest of the rope catch up
+    fn step(&mut self, delta: Posn) {
+        for idx in 0..self.knots.len() {
+            let moved = match idx {
+                0 => self.knots[0] + delta,
+                _ => follow(self.knots[idx - 1], self.knots[idx], self.slack),
+            };
+            // Knots that stay put haven't arrived anywhere
+            if moved != self.knots[idx] {
+                *self.visits[idx].entry(moved).or_insert(0) += 1;
+                self.knots[idx] = moved;
+            }
+        }
+    }
+
+    fn apply(&mut self, mv: Move) {
+        for _ in 0..mv.count {
+            self.step(mv.direction.delta());
+        }
+    }
+
+    fn tail(&self) -> Posn {
+        *self.knots.last().unwrap()
+    }
+
+    /// Every position knot `knot` has been in, the head being knot 0
+    fn visited(&self, knot: usize) -> impl Iterator<Item = Posn> + '_ {
+        self.visits[knot].keys().copied()
+    }
 
-    let mut new_pos = head + unit;
-    if abs.x > abs.y {
-        // x direction dominates, y will end up level with head
-        new_pos.y = head.y;
+    fn stats(&self, knot: usize) -> TrailStats {
+        let visits = &self.visits[knot];
+        let (&most_visited, &most_visits) = visits
+            .iter()
+            .min_by_key(|&(p, &count)| (Reverse(count), Reverse(p.y), p.x))
+            .unwrap();
+        TrailStats {
+            bounds: self.visited(knot).collect(),
+            cells: visits.len(),
+            revisits: visits.values().sum::<usize>() - visits.len(),
+            most_visited,
+            most_visits,
+        }
     }
-    if abs.x < abs.y {
-        // y direction dominates so x will end up level with head
-        new_pos.x = head.x;
+
+    /// Bounds of everywhere any knot has been
+    fn bounds(&self) -> Bounds {
+        (0..self.knots.len())
+            .flat_map(|knot| self.visited(knot))
+            .collect()
+    }
+
+    /// Where knot `knot` has been, with the start marked `s`
+    fn trail(&self, knot: usize, bounds: Bounds) -> Array2D<Cell> {
+        let mut grid = Array2D::filled_with(Cell::Empty, bounds.height(), bounds.width());
+        for posn in self.visited(knot) {
+            if let Some(at) = bounds.index(posn) {
+                grid[at] = Cell::Trail;
+            }
+        }
+        if let Some(at) = bounds.index(Posn::default()) {
+            grid[at] = Cell::Start;
+        }
+        grid
+    }
+
+    /// Draw the knots the way the puzzle does, with `H` for the head, on top
+    /// of the trail of knot `trail` if there is one
+    fn draw(&self, bounds: Bounds, trail: Option<usize>) -> Array2D<Cell> {
+        let mut grid = match trail {
+            Some(knot) => self.trail(knot, bounds),
+            None => {
+                let mut grid = Array2D::filled_with(Cell::Empty, bounds.height(), bounds.width());
+                if let Some(at) = bounds.index(Posn::default()) {
+                    grid[at] = Cell::Start;
+                }
+                grid
+            }
+        };
+        // Earlier knots cover later ones
+        for (idx, &knot) in self.knots.iter().enumerate().rev() {
+            if let Some(at) = bounds.index(knot) {
+                grid[at] = Cell::Knot(idx, self.knots.len());
+            }
+        }
+        grid
     }
-    new_pos
 }
 
-// Apply all moves for a line and update head and tail. Track all tail locations visited
-fn execute_line(line: &str, rope: &mut Vec<Posn>, visited: &mut HashSet<Posn>) {
-    let (_, (direction, count)) = parse_move(line).unwrap();
-    let delta = match direction {
-        Direction::Up => Posn { x: 0, y: 1 },
-        Direction::Down => Posn { x: 0, y: -1 },
-        Direction::Left => Posn { x: -1, y: 0 },
-        Direction::Right => Posn { x: 1, y: 0 },
-    };
-    for _ in 0..count {
-        // Move the very head of the rope first
-        rope[0] = rope[0] + delta;
-        for idx in 1..rope.len() {
-            let lead = rope[idx - 1];
-            let follow = rope[idx];
-            rope[idx] = move_tail(lead, follow);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Trail,
+    Start,
+    /// Knot number and how many knots there are
+    Knot(usize, usize),
+}
+
+impl Pixel for Cell {
+    fn glyph(&self) -> char {
+        match *self {
+            Cell::Empty => '.',
+            Cell::Trail => '#',
+            Cell::Start => 's',
+            Cell::Knot(0, _) => 'H',
+            Cell::Knot(_, 2) => 'T',
+            Cell::Knot(idx, _) => char::from_digit(idx as u32, 36).unwrap_or('*'),
+        }
+    }
+
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            Cell::Empty => [0x0f, 0x0f, 0x23],
+            Cell::Trail => [0x3a, 0x5a, 0x40],
+            Cell::Start => [0x99, 0x99, 0xcc],
+            Cell::Knot(0, _) => [0xff, 0x44, 0x44],
+            Cell::Knot(..) => [0xff, 0xff, 0x66],
         }
-        visited.insert(rope.last().unwrap().clone());
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let start_pos = Posn::default();
-    let mut locations = HashSet::<Posn>::new();
-    let mut rope: Vec<Posn> = vec![start_pos; 2];
-    locations.insert(rope.last().unwrap().clone());
+/// The smallest rectangle holding a set of positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    min: Posn,
+    max: Posn,
+}
+
+impl FromIterator<Posn> for Bounds {
+    fn from_iter<I: IntoIterator<Item = Posn>>(iter: I) -> Self {
+        let mut bounds = Bounds {
+            min: Posn::default(),
+            max: Posn::default(),
+        };
+        for posn in iter {
+            bounds.min.x = bounds.min.x.min(posn.x);
+            bounds.min.y = bounds.min.y.min(posn.y);
+            bounds.max.x = bounds.max.x.max(posn.x);
+            bounds.max.y = bounds.max.y.max(posn.y);
+        }
+        bounds
+    }
+}
 
-    for line in input.lines() {
-        execute_line(line, &mut rope, &mut locations);
+impl Bounds {
+    fn width(&self) -> usize {
+        (self.max.x - self.min.x) as usize + 1
     }
 
-    Some(locations.len() as u32)
+    fn height(&self) -> usize {
+        (self.max.y - self.min.y) as usize + 1
+    }
+
+    /// Grid (row, column) of a position, with up being towards row 0
+    fn index(&self, posn: Posn) -> Option<(usize, usize)> {
+        let inside = (self.min.x..=self.max.x).contains(&posn.x)
+            && (self.min.y..=self.max.y).contains(&posn.y);
+        inside.then(|| {
+            (
+                (self.max.y - posn.y) as usize,
+                (posn.x - self.min.x) as usize,
+            )
+        })
+    }
+}
+
+/// Where a knot has been. The bounds always include the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrailStats {
+    bounds: Bounds,
+    cells: usize,
+    /// Arrivals at a cell the knot had already been in
+    revisits: usize,
+    /// The cell arrived at most often, the top left one if there's a tie
+    most_visited: Posn,
+    most_visits: usize,
+}
+
+fn tail_positions(input: &str, knots: usize) -> Option<u32> {
+    let mut rope = Rope::new(knots);
+    for mv in parse_moves(input).unwrap() {
+        rope.apply(mv);
+    }
+    Some(rope.visited(knots - 1).count() as u32)
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    tail_positions(input, 2)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let start_pos = Posn::default();
-    let mut locations = HashSet::<Posn>::new();
-    let mut rope: Vec<Posn> = vec![start_pos; 10];
-    locations.insert(rope.last().unwrap().clone());
+    tail_positions(input, 10)
+}
 
-    for line in input.lines() {
-        execute_line(line, &mut rope, &mut locations);
+fn run(input: &str, knots: usize, slack: i32) -> Rope {
+    let mut rope = Rope::with_slack(knots, slack);
+    for mv in parse_moves(input).unwrap() {
+        rope.apply(mv);
     }
+    rope
+}
 
-    Some(locations.len() as u32)
+/// Where each knot of a rope goes
+fn report(input: &str, knots: usize, slack: i32) {
+    let rope = run(input, knots, slack);
+    for knot in 0..knots {
+        let stats = rope.stats(knot);
+        println!(
+            "knot {}: {} positions visited within {:?} to {:?}, {} revisits, most often {:?} ({} times)",
+            knot,
+            stats.cells,
+            stats.bounds.min,
+            stats.bounds.max,
+            stats.revisits,
+            stats.most_visited,
+            stats.most_visits
+        );
+    }
+    println!("tail finished at {:?}", rope.tail());
+}
+
+/// The rope after every instruction, like the puzzle's step by step diagrams
+fn diagrams(input: &str, knots: usize, slack: i32) -> String {
+    let bounds = run(input, knots, slack).bounds();
+    let mut rope = Rope::with_slack(knots, slack);
+    let mut text = String::new();
+    for mv in parse_moves(input).unwrap() {
+        rope.apply(mv);
+        text += &format!("== {} ==\n\n{}\n", mv, to_text(&rope.draw(bounds, None)));
+    }
+    text
+}
+
+/// The trail each knot leaves, drawn on its own
+fn trails(input: &str, knots: usize, slack: i32) -> Vec<String> {
+    let rope = run(input, knots, slack);
+    (0..knots)
+        .map(|knot| to_text(&rope.trail(knot, rope.stats(knot).bounds)))
+        .collect()
+}
+
+fn visualize(input: &str, visualizer: &mut Visualizer, knots: usize, slack: i32) {
+    let bounds = run(input, knots, slack).bounds();
+    let mut rope = Rope::with_slack(knots, slack);
+    visualizer.frame(&rope.draw(bounds, Some(knots - 1)));
+    for mv in parse_moves(input).unwrap() {
+        for _ in 0..mv.count {
+            rope.step(mv.direction.delta());
+            visualizer.frame(&rope.draw(bounds, Some(knots - 1)));
+        }
+    }
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 9);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    let knots: Option<usize> = args.opt_value_from_str("--knots").unwrap();
+    let slack = args.opt_value_from_str("--slack").unwrap().unwrap_or(1);
+    if let Some(knots) = knots {
+        report(input, knots, slack);
+    }
+    if args.contains("--diagrams") {
+        print!("{}", diagrams(input, knots.unwrap_or(2), slack));
+    }
+    if args.contains("--trails") {
+        for (knot, trail) in trails(input, knots.unwrap_or(2), slack).iter().enumerate() {
+            println!("knot {}:\n{}", knot, trail);
+        }
+    }
+    if let Some(mut visualizer) = Visualizer::from_args() {
+        visualize(input, &mut visualizer, knots.unwrap_or(10), slack);
+    }
 }
 
 #[cfg(test)]
@@ -161,55 +476,147 @@ mod tests {
     }
 
     #[test]
-    fn test_move_tail() {
+    fn test_follow() {
         let head = Posn { x: 0, y: 0 };
         assert_eq!(
-            move_tail(head, Posn { x: -1, y: -1 }),
+            follow(head, Posn { x: -1, y: -1 }, 1),
             Posn { x: -1, y: -1 }
         );
         let head = Posn { x: 0, y: 0 };
         let tail = Posn { x: -2, y: -1 };
         let expected = Posn { x: -1, y: 0 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 0, y: 0 };
         let tail = Posn { x: 2, y: 2 };
         let expected = Posn { x: 1, y: 1 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 0, y: 0 };
         let tail = Posn { x: 2, y: -2 };
         let expected = Posn { x: 1, y: -1 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 4, y: 2 };
         let tail = Posn { x: 3, y: 0 };
         let expected = Posn { x: 4, y: 1 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 5, y: 3 };
         let tail = Posn { x: 3, y: 4 };
         let expected = Posn { x: 4, y: 3 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 5, y: 3 };
         let tail = Posn { x: 4, y: 5 };
         let expected = Posn { x: 5, y: 4 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 5, y: 3 };
         let tail = Posn { x: 6, y: 5 };
         let expected = Posn { x: 5, y: 4 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 5, y: 3 };
         let tail = Posn { x: 7, y: 2 };
         let expected = Posn { x: 6, y: 3 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
 
         let head = Posn { x: 5, y: 3 };
         let tail = Posn { x: 2, y: 2 };
         let expected = Posn { x: 4, y: 3 };
-        assert_eq!(move_tail(head, tail), expected);
+        assert_eq!(follow(head, tail, 1), expected);
+    }
+
+    #[test]
+    fn test_every_knot() {
+        let mut rope = Rope::new(10);
+        for mv in parse_moves(&aoc::read_file("examples", 9)).unwrap() {
+            rope.apply(mv);
+        }
+        // The knot behind the head moves like the tail of a two knot rope
+        assert_eq!(rope.visited(1).count(), 13);
+        assert_eq!(rope.visited(9).count(), 1);
+        assert_eq!(rope.tail(), Posn::default());
+    }
+
+    #[test]
+    fn test_diagonal_and_slack() {
+        let mut rope = Rope::new(2);
+        rope.apply(Move {
+            direction: Direction::UpRight,
+            count: 3,
+        });
+        assert_eq!(rope.tail(), Posn { x: 2, y: 2 });
+        assert_eq!(rope.visited(1).count(), 3);
+
+        let mut rope = Rope::with_slack(3, 2);
+        for mv in parse_moves("R 4\nDL 1").unwrap() {
+            rope.apply(mv);
+        }
+        assert_eq!(
+            rope.knots,
+            vec![Posn { x: 3, y: -1 }, Posn { x: 2, y: 0 }, Posn::default()]
+        );
+        assert_eq!(rope.visited(1).count(), 3);
+        assert_eq!(rope.visited(2).count(), 1);
+    }
+
+    #[test]
+    fn test_bad_moves() {
+        assert_eq!(
+            parse_moves("R 4\nF 2").err(),
+            Some("line 2: unknown direction \"F\"".to_owned())
+        );
+        assert_eq!(
+            parse_moves("R 4\nU\n").err(),
+            Some("line 2: can't understand \"U\"".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_larger_example() {
+        let input = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+        assert_eq!(part_two(input), Some(36));
+        let first = diagrams(input, 10, 1)
+            .split("\n\n")
+            .nth(1)
+            .unwrap()
+            .to_owned();
+        // The start is covered by the knots that haven't moved yet
+        assert_eq!(first.lines().nth(15), Some("...........54321H........."));
+    }
+
+    #[test]
+    fn test_diagrams() {
+        let input = aoc::read_file("examples", 9);
+        assert!(diagrams(&input, 2, 1).starts_with(
+            "== R 4 ==\n\n......\n......\n......\n......\ns..TH.\n\n\
+             == U 4 ==\n\n....H.\n....T.\n......\n......\ns.....\n\n"
+        ));
+        assert_eq!(
+            trails(&input, 2, 1)[1],
+            "..##.\n...##\n.####\n....#\ns###.\n"
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let rope = run(&aoc::read_file("examples", 9), 2, 1);
+        assert_eq!(
+            rope.stats(1),
+            TrailStats {
+                bounds: Bounds {
+                    min: Posn { x: 0, y: 0 },
+                    max: Posn { x: 4, y: 4 }
+                },
+                cells: 13,
+                revisits: 1,
+                most_visited: Posn { x: 4, y: 3 },
+                most_visits: 2,
+            }
+        );
+        assert_eq!(rope.bounds().width(), 6);
+        assert_eq!(rope.bounds().height(), 5);
     }
 }
@@ -1,120 +1,266 @@
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::{i32 as nom32, newline};
-use nom::multi::separated_list1;
-use nom::sequence::separated_pair;
-use nom::IResult;
-
-use std::collections::VecDeque;
-
-#[derive(Debug)]
-enum OpType {
-    Add,
-    Processing,
-    Noop,
-}
-
-#[derive(Debug)]
-struct Op {
-    op_type: OpType,
-    arg: i32,
-}
-
-fn noop(input: &str) -> IResult<&str, Op> {
-    let (input, _) = tag("noop")(input)?;
-    Ok((
-        input,
-        Op {
-            op_type: OpType::Noop,
-            arg: 0,
-        },
-    ))
-}
-fn addx(input: &str) -> IResult<&str, Op> {
-    let (input, (_, arg)) = separated_pair(tag("addx"), tag(" "), nom32)(input)?;
-    Ok((
-        input,
-        Op {
-            op_type: OpType::Add,
-            arg: arg,
-        },
-    ))
-}
-
-fn parse_line(input: &str) -> IResult<&str, Op> {
-    alt((addx, noop))(input)
-}
-
-fn parse_lines(input: &str) -> IResult<&str, VecDeque<Op>> {
-    let (input, ops) = separated_list1(newline, parse_line)(input)?;
-    let mut op_queue = VecDeque::<Op>::new();
-    for op in ops {
-        match op.op_type {
-            OpType::Add => {
-                op_queue.push_back(Op {
-                    op_type: OpType::Processing,
-                    arg: 0,
-                });
-                op_queue.push_back(op);
-            }
-            _ => {
-                op_queue.push_back(op);
-            }
-        }
+use aoc::helpers::ocr;
+use aoc::helpers::visualize::{to_text, Visualizer};
+use array2d::Array2D;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The CPU's registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Registers {
+    x: i32,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers { x: 1 }
     }
-    Ok((input, op_queue))
 }
 
-fn register_for_cycles(input: &str) -> Vec<i32> {
-    let (_, mut ops) = parse_lines(input).unwrap();
-    let mut reg_over_time: Vec<i32> = Vec::new();
-    let mut current_reg = 1;
-    ops.push_front(Op {
-        op_type: OpType::Processing,
-        arg: 0,
-    });
-    reg_over_time.push(current_reg);
-    for op in ops {
-        current_reg += op.arg;
-        reg_over_time.push(current_reg);
+/// How an instruction is written, how long it takes and what it does once
+/// its last cycle is over
+struct Spec {
+    mnemonic: &'static str,
+    args: usize,
+    cycles: usize,
+    execute: fn(&mut Registers, &[i32]),
+}
+
+/// The instruction set, new instructions just need adding here
+const INSTRUCTIONS: &[Spec] = &[
+    Spec {
+        mnemonic: "noop",
+        args: 0,
+        cycles: 1,
+        execute: |_, _| {},
+    },
+    Spec {
+        mnemonic: "addx",
+        args: 1,
+        cycles: 2,
+        execute: |registers, args| registers.x += args[0],
+    },
+];
+
+struct Instruction {
+    spec: &'static Spec,
+    args: Vec<i32>,
+}
+
+impl std::fmt::Debug for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?}", self.spec.mnemonic, self.args)
     }
-    reg_over_time
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let reg_over_time = register_for_cycles(input);
-    let score = reg_over_time
-        .iter()
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let mnemonic = words.next().ok_or("there's no instruction")?;
+        let spec = INSTRUCTIONS
+            .iter()
+            .find(|spec| spec.mnemonic == mnemonic)
+            .ok_or(format!("unknown instruction \"{}\"", mnemonic))?;
+        let args = words
+            .map(|word| {
+                word.parse::<i32>()
+                    .map_err(|_| format!("\"{}\" isn't a number", word))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if args.len() != spec.args {
+            return Err(format!(
+                "{} takes {} arguments but was given {}",
+                mnemonic,
+                spec.args,
+                args.len()
+            ));
+        }
+        Ok(Instruction { spec, args })
+    }
+}
+
+fn parse_program(input: &str) -> Result<Vec<Instruction>, String> {
+    input
+        .lines()
         .enumerate()
-        .skip(20)
-        .step_by(40)
-        .map(|(idx, value)| idx as i32 * value)
-        .sum::<i32>();
-    Some(score as u32)
-}
-
-pub fn part_two(input: &str) -> Option<u32> {
-    let reg_over_time = register_for_cycles(input);
-    let mut row = vec![" "; 40];
-    for idx in 0..240 {
-        let x_pos = idx % 40;
-        if x_pos == 39 {
-            // Print out and reset row
-            println!("{}", row.clone().into_iter().collect::<String>());
-            row.fill(" ");
+        .map(|(idx, line)| {
+            line.parse::<Instruction>()
+                .map_err(|e| format!("line {}: {}", idx + 1, e))
+        })
+        .collect()
+}
+
+/// Something that wants to know what the registers hold during each cycle
+trait Observer {
+    fn during(&mut self, cycle: usize, registers: &Registers);
+}
+
+impl<F: FnMut(usize, &Registers)> Observer for F {
+    fn during(&mut self, cycle: usize, registers: &Registers) {
+        self(cycle, registers)
+    }
+}
+
+/// Runs a program a cycle at a time. An instruction only changes the
+/// registers once its last cycle is over.
+struct Cpu<'a> {
+    program: &'a [Instruction],
+    registers: Registers,
+    cycle: usize,
+    pc: usize,
+    elapsed: usize,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: &'a [Instruction]) -> Self {
+        Cpu {
+            program,
+            registers: Registers::default(),
+            cycle: 0,
+            pc: 0,
+            elapsed: 0,
+        }
+    }
+
+    /// Run a single cycle, returning false if the program has already finished
+    fn tick(&mut self, observer: &mut impl Observer) -> bool {
+        let Some(instruction) = self.program.get(self.pc) else {
+            return false;
+        };
+        self.cycle += 1;
+        observer.during(self.cycle, &self.registers);
+        self.elapsed += 1;
+        if self.elapsed >= instruction.spec.cycles {
+            (instruction.spec.execute)(&mut self.registers, &instruction.args);
+            self.pc += 1;
+            self.elapsed = 0;
+        }
+        true
+    }
+
+    fn run(&mut self, observer: &mut impl Observer) {
+        while self.tick(observer) {}
+    }
+}
+
+/// Adds up the signal strength, the cycle number times x, during cycle
+/// `first` and every `every` cycles after it
+struct SignalStrength {
+    first: usize,
+    every: usize,
+    total: i64,
+}
+
+impl Observer for SignalStrength {
+    fn during(&mut self, cycle: usize, registers: &Registers) {
+        if cycle >= self.first && (cycle - self.first).is_multiple_of(self.every) {
+            self.total += cycle as i64 * registers.x as i64;
+        }
+    }
+}
+
+pub fn part_one(input: &str) -> Option<i64> {
+    let program = parse_program(input).unwrap();
+    let mut strength = SignalStrength {
+        first: 20,
+        every: 40,
+        total: 0,
+    };
+    Cpu::new(&program).run(&mut strength);
+    Some(strength.total)
+}
+
+/// A screen drawn a pixel per cycle, left to right and top to bottom. The
+/// pixel being drawn is lit if the sprite, centred on x, covers it.
+struct Crt {
+    pixels: Array2D<bool>,
+    sprite_width: usize,
+}
+
+impl Crt {
+    fn new(width: usize, height: usize, sprite_width: usize) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("a {}x{} screen has no pixels", width, height));
         }
-        let r = reg_over_time[idx + 1];
-        if (r - x_pos as i32).abs() <= 1 {
-            row[x_pos] = "#";
+        Ok(Crt {
+            pixels: Array2D::filled_with(false, height, width),
+            sprite_width,
+        })
+    }
+
+    fn pixels(&self) -> &Array2D<bool> {
+        &self.pixels
+    }
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Crt::new(40, 6, 3).unwrap()
+    }
+}
+
+impl Observer for Crt {
+    fn during(&mut self, cycle: usize, registers: &Registers) {
+        let width = self.pixels.num_columns();
+        let (row, col) = ((cycle - 1) / width, (cycle - 1) % width);
+        if row >= self.pixels.num_rows() {
+            return;
         }
+        // An even width sprite reaches further right than left
+        let left = registers.x as i64 - (self.sprite_width as i64 - 1) / 2;
+        let right = registers.x as i64 + self.sprite_width as i64 / 2;
+        self.pixels[(row, col)] = (left..=right).contains(&(col as i64));
     }
-    None
+}
+
+impl Display for Crt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", to_text(&self.pixels))
+    }
+}
+
+/// The screen once `input` has finished running
+fn draw(input: &str, mut crt: Crt) -> Crt {
+    let program = parse_program(input).unwrap();
+    Cpu::new(&program).run(&mut crt);
+    crt
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    let crt = draw(input, Crt::default());
+    ocr::recognise_grid(crt.pixels()).ok()
+}
+
+fn visualize(input: &str, visualizer: &mut Visualizer) {
+    let program = parse_program(input).unwrap();
+    let mut crt = Crt::default();
+    // Show the screen as each pixel is drawn
+    Cpu::new(&program).run(&mut |cycle: usize, registers: &Registers| {
+        crt.during(cycle, registers);
+        visualizer.frame(crt.pixels());
+    });
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 10);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+    if let Some(mut visualizer) = Visualizer::from_args() {
+        visualize(input, &mut visualizer);
+    }
+
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--screen") {
+        let width = args.opt_value_from_str("--width").unwrap().unwrap_or(40);
+        let height = args.opt_value_from_str("--height").unwrap().unwrap_or(6);
+        let sprite = args.opt_value_from_str("--sprite").unwrap().unwrap_or(3);
+        match Crt::new(width, height, sprite) {
+            Ok(crt) => print!("{}", draw(input, crt)),
+            Err(e) => println!("{}", e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -132,7 +278,7 @@ mod tests {
         let input = "noop
 addx 3
 addx -5";
-        assert_eq!(part_one(&input), Some(0));
+        assert_eq!(part_one(input), Some(0));
     }
 
     #[test]
@@ -140,4 +286,83 @@ addx -5";
         let input = aoc::read_file("examples", 10);
         assert_eq!(part_two(&input), None);
     }
+
+    #[test]
+    fn test_part_two_letters() {
+        let input = include_str!("../examples/10_letters.txt");
+        assert_eq!(part_two(input), Some("FIREBALL".to_owned()));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let program = parse_program("noop\naddx 3\naddx -5").unwrap();
+        let mut seen = Vec::new();
+        Cpu::new(&program)
+            .run(&mut |cycle: usize, registers: &Registers| seen.push((cycle, registers.x)));
+        assert_eq!(seen, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+    }
+
+    #[test]
+    fn test_bad_program() {
+        assert_eq!(
+            parse_program("noop\nmulx 3").err(),
+            Some("line 2: unknown instruction \"mulx\"".to_owned())
+        );
+        assert_eq!(
+            parse_program("addx").err(),
+            Some("line 1: addx takes 1 arguments but was given 0".to_owned())
+        );
+        assert_eq!(
+            parse_program("addx y").err(),
+            Some("line 1: \"y\" isn't a number".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_screen() {
+        let input = aoc::read_file("examples", 10);
+        let crt = draw(&input, Crt::default());
+        assert_eq!(
+            crt.to_string(),
+            "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....
+"
+        );
+        // A narrower screen starts the same but has more rows
+        let narrow = draw(&input, Crt::new(20, 12, 3).unwrap()).to_string();
+        assert_eq!(narrow.lines().next(), Some("##..##..##..##..##.."));
+        assert_eq!(narrow.lines().count(), 12);
+    }
+
+    #[test]
+    fn test_sprite_width() {
+        let input = "noop\nnoop\nnoop\nnoop\nnoop";
+        assert_eq!(
+            draw(input, Crt::new(5, 1, 1).unwrap()).to_string(),
+            ".#...\n"
+        );
+        assert_eq!(
+            draw(input, Crt::new(5, 1, 3).unwrap()).to_string(),
+            "###..\n"
+        );
+        assert_eq!(
+            draw(input, Crt::new(5, 1, 4).unwrap()).to_string(),
+            "####.\n"
+        );
+        // Cycles beyond the screen aren't drawn and missing ones stay dark
+        assert_eq!(draw(input, Crt::new(2, 1, 3).unwrap()).to_string(), "##\n");
+        assert_eq!(
+            draw(input, Crt::new(4, 2, 3).unwrap()).to_string(),
+            "###.\n#...\n"
+        );
+        assert_eq!(
+            Crt::new(0, 6, 3).err(),
+            Some("a 0x6 screen has no pixels".to_owned())
+        );
+        assert!(Crt::new(40, 0, 3).is_err());
+    }
 }
@@ -1,95 +1,234 @@
+use std::fmt::Display;
+
 use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{newline, u32 as nom32, u64 as nom64};
-use nom::multi::separated_list0;
-use nom::sequence::delimited;
-use nom::sequence::preceded;
-use nom::sequence::terminated;
+use nom::character::complete::{newline, one_of, space0, u32 as nom32, u64 as nom64};
+use nom::combinator::{all_consuming, map, value};
+use nom::multi::{fold_many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, terminated};
 use nom::IResult;
+use num_bigint::BigUint;
+
+/// Worry levels get big, and without a modulus to keep them down there's no
+/// limit to how big, so they're arbitrary precision
+type Worry = BigUint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    /// Apply the operator, keeping the result below `modulus` if there is one
+    fn apply(self, a: Worry, b: Worry, modulus: Option<&Worry>) -> Result<Worry, String> {
+        let (a, b) = match modulus {
+            Some(m) => (a % m, b % m),
+            None => (a, b),
+        };
+        let result = match (self, modulus) {
+            (Operator::Add, _) => a + b,
+            (Operator::Sub, None) if a < b => return Err(format!("{} - {} is negative", a, b)),
+            (Operator::Sub, None) => a - b,
+            (Operator::Sub, Some(m)) => a + (m - b),
+            (Operator::Mul, _) => a * b,
+            (Operator::Div, None) if b == Worry::ZERO => return Err(format!("{} / 0", a)),
+            (Operator::Div, None) => a / b,
+            // Rounding down has no meaning in modular arithmetic
+            (Operator::Div, Some(m)) => return Err(format!("can't divide modulo {}", m)),
+        };
+        Ok(match modulus {
+            Some(m) => result % m,
+            None => result,
+        })
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Operator::Add => '+',
+            Operator::Sub => '-',
+            Operator::Mul => '*',
+            Operator::Div => '/',
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// The right hand side of a monkey's `new = ...` operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Const(Worry),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, old: &Worry, modulus: Option<&Worry>) -> Result<Worry, String> {
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Const(n) => Ok(n.clone()),
+            Expr::Binary(op, a, b) => {
+                op.apply(a.eval(old, modulus)?, b.eval(old, modulus)?, modulus)
+            }
+        }
+    }
+
+    fn divides(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => false,
+            Expr::Binary(op, a, b) => *op == Operator::Div || a.divides() || b.divides(),
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Const(n) => write!(f, "{}", n),
+            Expr::Binary(op, a, b) => {
+                // Bracket anything that isn't a single value, to be safe
+                let side = |e: &Expr| match e {
+                    Expr::Binary(..) => format!("({})", e),
+                    _ => e.to_string(),
+                };
+                write!(f, "{} {} {}", side(a), op, side(b))
+            }
+        }
+    }
+}
+
+fn atom(input: &str) -> IResult<&str, Expr> {
+    delimited(
+        space0,
+        alt((
+            value(Expr::Old, tag("old")),
+            map(nom64, |n| Expr::Const(n.into())),
+            delimited(tag("("), expr, tag(")")),
+        )),
+        space0,
+    )(input)
+}
+
+fn operator(input: &str) -> IResult<&str, Operator> {
+    map(one_of("+-*/"), |c| match c {
+        '+' => Operator::Add,
+        '-' => Operator::Sub,
+        '*' => Operator::Mul,
+        _ => Operator::Div,
+    })(input)
+}
+
+/// Left associative chains of `atom`s joined by operators in `ops`
+fn chain<'a>(
+    ops: &'static [Operator],
+    operand: fn(&'a str) -> IResult<&'a str, Expr>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Expr> {
+    move |input| {
+        let (input, first) = operand(input)?;
+        fold_many0(
+            pair(
+                nom::combinator::verify(operator, |op| ops.contains(op)),
+                operand,
+            ),
+            move || first.clone(),
+            |acc, (op, rhs)| Expr::Binary(op, Box::new(acc), Box::new(rhs)),
+        )(input)
+    }
+}
+
+fn term(input: &str) -> IResult<&str, Expr> {
+    chain(&[Operator::Mul, Operator::Div], atom)(input)
+}
 
-#[derive(Debug)]
+fn expr(input: &str) -> IResult<&str, Expr> {
+    chain(&[Operator::Add, Operator::Sub], term)(input)
+}
+
+#[derive(Debug, Clone)]
 struct Monkey {
-    items: Vec<u64>,
-    power: u32,
-    multiply: u32,
-    add: u32,
-    divisible: u32,
+    items: Vec<Worry>,
+    operation: Expr,
+    divisible: Worry,
     positive_target: usize,
     negative_target: usize,
-    inspected_count: u32,
+    inspected_count: u64,
 }
 
 impl Monkey {
-    fn inspect(self: &mut Self, magic: u32) -> Vec<(usize, u64)> {
-        self.items
-            .drain(..)
-            .map(|item| {
-                // Monkey inspects
-                let mut new_item = item;
-                self.inspected_count += 1;
-                new_item = new_item.pow(self.power);
-                new_item *= self.multiply as u64;
-                new_item += self.add as u64;
-
-                // Use Chinese remainder theorem
-                if magic == 0 {
-                    // Not valid denom to do drop
-                    new_item /= 3;
-                } else {
-                    new_item = new_item % magic as u64;
-                }
-
-                // Find target
-                let mut destination = self.negative_target;
-                if new_item % self.divisible as u64 == 0 {
-                    destination = self.positive_target
-                }
-                (destination, new_item)
-            })
-            .collect()
+    // Work out the new worry level for an item and which monkey it gets thrown to
+    fn inspect_item(
+        &self,
+        item: Worry,
+        relief: u32,
+        modulus: Option<&Worry>,
+    ) -> Result<Throw, String> {
+        let operated = self.operation.eval(&item, modulus)?;
+        let worry = match modulus {
+            Some(_) => operated.clone(),
+            None => &operated / relief,
+        };
+        let divisible = &worry % &self.divisible == Worry::ZERO;
+        let to = if divisible {
+            self.positive_target
+        } else {
+            self.negative_target
+        };
+        Ok(Throw {
+            old: item,
+            operated,
+            worry,
+            divisible,
+            to,
+        })
     }
 }
 
-fn power(input: &str) -> IResult<&str, (u32, u32, u32)> {
-    let (input, _) = tag("* old")(input)?;
-    Ok((input, (2, 1, 0)))
+/// What happened to an item when a monkey inspected it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Throw {
+    old: Worry,
+    /// Worry level once the monkey's operation is done
+    operated: Worry,
+    /// Worry level after any relief, as the item is thrown
+    worry: Worry,
+    divisible: bool,
+    to: usize,
 }
-fn multiply(input: &str) -> IResult<&str, (u32, u32, u32)> {
-    let (input, value) = preceded(tag("* "), nom32)(input)?;
-    Ok((input, (1, value, 0)))
-}
-fn add(input: &str) -> IResult<&str, (u32, u32, u32)> {
-    let (input, add) = preceded(tag("+ "), nom32)(input)?;
-    Ok((input, (1, 1, add)))
-}
-fn parse_op(input: &str) -> IResult<&str, (u32, u32, u32)> {
-    let (input, (power, multiply, add)) = terminated(alt((power, multiply, add)), newline)(input)?;
-    Ok((input, (power, multiply, add)))
+
+/// Things that happen during a round
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    /// A monkey starts its turn
+    Turn(usize),
+    Throw(Throw),
 }
 
 fn parse_monkey(input: &str) -> IResult<&str, Monkey> {
     let (input, _monkey_num) = delimited(tag("Monkey "), nom32, tag(":"))(input)?;
     let (input, _) = newline(input)?;
     let (input, _starting_text) = tag("  Starting items: ")(input)?;
-    let (input, items) = terminated(separated_list0(tag(", "), nom64), newline)(input)?;
-    let (input, _operation_text) = tag("  Operation: new = old ")(input)?;
-    let (input, (power, multiply, add)) = parse_op(input)?;
-    let (input, _test) = tag("  Test: divisible by ")(input)?;
-    let (input, divisible) = nom32(input)?;
-    let (input, _) = newline(input)?;
-    let (input, _true) = tag("    If true: throw to monkey ")(input)?;
-    let (input, positive_target) = nom32(input)?;
-    let (input, _) = newline(input)?;
-    let (input, _true) = tag("    If false: throw to monkey ")(input)?;
-    let (input, negative_target) = nom32(input)?;
+    let (input, items) =
+        terminated(separated_list0(tag(", "), map(nom64, Worry::from)), newline)(input)?;
+    let (input, operation) = delimited(tag("  Operation: new ="), expr, newline)(input)?;
+    let (input, divisible) = delimited(
+        tag("  Test: divisible by "),
+        map(nom64, Worry::from),
+        newline,
+    )(input)?;
+    let (input, positive_target) =
+        delimited(tag("    If true: throw to monkey "), nom32, newline)(input)?;
+    let (input, negative_target) = preceded(tag("    If false: throw to monkey "), nom32)(input)?;
     let (input, _) = newline(input)?;
 
     let monkey = Monkey {
         items,
-        power,
-        multiply,
-        add,
+        operation,
         divisible,
         positive_target: positive_target as usize,
         negative_target: negative_target as usize,
@@ -98,55 +237,275 @@ fn parse_monkey(input: &str) -> IResult<&str, Monkey> {
     Ok((input, monkey))
 }
 
-fn parse_monkeys(input: &str) -> IResult<&str, Vec<Monkey>> {
-    separated_list0(newline, parse_monkey)(input)
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, String> {
+    let (_, monkeys) = all_consuming(separated_list0(newline, parse_monkey))(input)
+        .map_err(|e| format!("can't understand the monkeys: {}", e))?;
+    for (idx, monkey) in monkeys.iter().enumerate() {
+        for target in [monkey.positive_target, monkey.negative_target] {
+            if target >= monkeys.len() || target == idx {
+                return Err(format!("monkey {} can't throw to monkey {}", idx, target));
+            }
+        }
+        if monkey.divisible == Worry::ZERO {
+            return Err(format!("monkey {} tests for divisibility by 0", idx));
+        }
+    }
+    Ok(monkeys)
 }
 
-fn process(input: &str, use_magic: bool, rounds: u32) -> u64 {
-    let (_, mut monkeys) = parse_monkeys(input).unwrap();
-    let mut magic = monkeys.iter().map(|m| m.divisible).product();
-    if !use_magic {
-        magic = 0
+fn gcd(a: &Worry, b: &Worry) -> Worry {
+    if *b == Worry::ZERO {
+        a.clone()
+    } else {
+        gcd(b, &(a % b))
     }
-    for _ in 0..rounds {
-        for mky_idx in 0..monkeys.len() {
-            let items = monkeys[mky_idx].inspect(magic);
+}
+
+fn lcm(a: &Worry, b: &Worry) -> Worry {
+    a / gcd(a, b) * b
+}
+
+/// All the monkeys, playing keep away a round at a time
+#[derive(Debug, Clone)]
+struct Troop {
+    monkeys: Vec<Monkey>,
+    /// What worry levels are divided by after an inspection
+    relief: u32,
+    /// Worry levels are kept below this when it's safe to, since only their
+    /// divisibility matters
+    modulus: Option<Worry>,
+    rounds: usize,
+}
+
+impl Troop {
+    fn new(monkeys: Vec<Monkey>, relief: u32) -> Self {
+        // Working modulo a multiple of every divisor keeps all the tests
+        // right, but dividing and rounding down doesn't survive it so
+        // levels are kept whole if there's any of that to do. Working modulo
+        // relief times that only gets one division right: afterwards the
+        // level is only known modulo the smaller number again.
+        let divides = relief != 1 || monkeys.iter().any(|m| m.operation.divides());
+        let modulus = (!divides).then(|| {
+            monkeys
+                .iter()
+                .fold(Worry::from(1u32), |l, m| lcm(&l, &m.divisible))
+        });
+        Troop {
+            monkeys,
+            relief,
+            modulus,
+            rounds: 0,
+        }
+    }
+
+    fn round(&mut self) -> Result<(), String> {
+        self.round_with(|_| {})
+    }
+
+    /// Play a round, telling `on_event` about every turn and throw
+    fn round_with(&mut self, mut on_event: impl FnMut(Event)) -> Result<(), String> {
+        for idx in 0..self.monkeys.len() {
+            on_event(Event::Turn(idx));
+            let items = std::mem::take(&mut self.monkeys[idx].items);
+            self.monkeys[idx].inspected_count += items.len() as u64;
             for item in items {
-                monkeys[item.0].items.push(item.1);
+                let throw =
+                    self.monkeys[idx].inspect_item(item, self.relief, self.modulus.as_ref())?;
+                let (to, worry) = (throw.to, throw.worry.clone());
+                on_event(Event::Throw(throw));
+                self.monkeys[to].items.push(worry);
             }
         }
+        self.rounds += 1;
+        Ok(())
+    }
+
+    /// How many items each monkey has inspected so far
+    fn inspections(&self) -> Vec<u64> {
+        self.monkeys.iter().map(|m| m.inspected_count).collect()
+    }
+
+    /// The worry levels of the items each monkey holds
+    fn holdings(&self) -> Vec<&[Worry]> {
+        self.monkeys.iter().map(|m| m.items.as_slice()).collect()
+    }
+
+    /// The product of the two largest inspection counts
+    fn monkey_business(&self) -> u64 {
+        self.inspections().iter().sorted().rev().take(2).product()
     }
-    let total = monkeys
-        .iter()
-        .map(|m| m.inspected_count as u64)
-        .sorted()
-        .rev()
-        .take(2)
-        .product::<u64>();
-    total
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let product = process(input, false, 20);
-    dbg!(product);
-    Some(product as u32)
+fn process(input: &str, relief: u32, rounds: u32) -> Result<u64, String> {
+    let mut troop = Troop::new(parse_monkeys(input)?, relief);
+    for round in 1..=rounds {
+        troop
+            .round()
+            .map_err(|e| format!("round {}: {}", round, e))?;
+        aoc::trace!("items after round {}: {:?}", round, troop.holdings());
+    }
+    aoc::debug!(
+        "inspection counts after {} rounds: {:?}",
+        rounds,
+        troop.inspections()
+    );
+    Ok(troop.monkey_business())
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let product = process(input, true, 10_000);
-    dbg!(product);
-    Some(product as u32)
+/// The answer `process` gives, logging why if there isn't one
+fn answer(input: &str, relief: u32, rounds: u32) -> Option<u64> {
+    process(input, relief, rounds)
+        .map_err(|e| aoc::debug!("{}", e))
+        .ok()
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    answer(input, 3, 20)
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    answer(input, 1, 10_000)
+}
+
+/// Where the items are and how many each monkey has inspected, round by round
+fn report(input: &str, relief: u32, rounds: usize) {
+    let mut troop = Troop::new(parse_monkeys(input).unwrap(), relief);
+    while troop.rounds < rounds {
+        if let Err(e) = troop.round() {
+            println!("round {} went wrong: {}", troop.rounds + 1, e);
+            return;
+        }
+        println!("== After round {} ==", troop.rounds);
+        for (idx, (items, count)) in troop.holdings().iter().zip(troop.inspections()).enumerate() {
+            println!(
+                "Monkey {} ({}): {} inspected, holding {}",
+                idx,
+                troop.monkeys[idx].operation,
+                count,
+                items.iter().join(", ")
+            );
+        }
+    }
+    println!("monkey business: {}", troop.monkey_business());
+}
+
+/// How the puzzle describes an operation
+fn describe(operation: &Expr) -> String {
+    match operation {
+        Expr::Binary(op, a, b) if **a == Expr::Old => match (op, &**b) {
+            (Operator::Mul, Expr::Old) => "is multiplied by itself".to_owned(),
+            (Operator::Mul, Expr::Const(n)) => format!("is multiplied by {}", n),
+            (Operator::Add, Expr::Const(n)) => format!("increases by {}", n),
+            (Operator::Sub, Expr::Const(n)) => format!("decreases by {}", n),
+            (Operator::Div, Expr::Const(n)) => format!("is divided by {}", n),
+            _ => format!("becomes {}", operation),
+        },
+        _ => format!("becomes {}", operation),
+    }
+}
+
+/// Everything that happens, told the way the puzzle tells it
+fn narrate(input: &str, relief: u32, rounds: usize) -> Result<String, String> {
+    let mut troop = Troop::new(parse_monkeys(input)?, relief);
+    let mut text = String::new();
+    while troop.rounds < rounds {
+        let monkeys = troop.monkeys.clone();
+        let mut monkey = 0;
+        troop.round_with(|event| match event {
+            Event::Turn(idx) => {
+                monkey = idx;
+                text += &format!("Monkey {}:\n", idx);
+            }
+            Event::Throw(throw) => {
+                let m = &monkeys[monkey];
+                text += &format!(
+                    "  Monkey inspects an item with a worry level of {}.\n",
+                    throw.old
+                );
+                text += &format!(
+                    "    Worry level {} to {}.\n",
+                    describe(&m.operation),
+                    throw.operated
+                );
+                if relief != 1 {
+                    text += &format!(
+                        "    Monkey gets bored with item. Worry level is divided by {} to {}.\n",
+                        relief, throw.worry
+                    );
+                }
+                let not = if throw.divisible { "" } else { "not " };
+                text += &format!(
+                    "    Current worry level is {}divisible by {}.\n",
+                    not, m.divisible
+                );
+                text += &format!(
+                    "    Item with worry level {} is thrown to monkey {}.\n",
+                    throw.worry, throw.to
+                );
+            }
+        })?;
+        text += &format!(
+            "\nAfter round {}, the monkeys are holding items with these worry levels:\n",
+            troop.rounds
+        );
+        for (idx, items) in troop.holdings().iter().enumerate() {
+            text += &format!("Monkey {}: {}\n", idx, items.iter().join(", "));
+        }
+        text += "\n";
+    }
+    Ok(text)
+}
+
+/// A row for each monkey at the start and after each round, with how many
+/// items it has inspected and the worry levels of the ones it holds
+fn csv(input: &str, relief: u32, rounds: usize) -> Result<String, String> {
+    let mut troop = Troop::new(parse_monkeys(input)?, relief);
+    let mut text = "round,monkey,inspected,items\n".to_owned();
+    loop {
+        for (idx, (items, count)) in troop.holdings().iter().zip(troop.inspections()).enumerate() {
+            text += &format!(
+                "{},{},{},{}\n",
+                troop.rounds,
+                idx,
+                count,
+                items.iter().join(" ")
+            );
+        }
+        if troop.rounds == rounds {
+            return Ok(text);
+        }
+        troop.round()?;
+    }
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 11);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    if let Some(rounds) = args.opt_value_from_str("--rounds").unwrap() {
+        let relief = args.opt_value_from_str("--relief").unwrap().unwrap_or(3);
+        if args.contains("--trace") {
+            match narrate(input, relief, rounds) {
+                Ok(text) => print!("{}", text),
+                Err(e) => println!("{}", e),
+            }
+        } else if args.contains("--csv") {
+            match csv(input, relief, rounds) {
+                Ok(text) => print!("{}", text),
+                Err(e) => println!("{}", e),
+            }
+        } else {
+            report(input, relief, rounds);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc::helpers::cycle::CycleDetector;
 
     #[test]
     fn test_part_one() {
@@ -159,4 +518,195 @@ mod tests {
         let input = aoc::read_file("examples", 11);
         assert_eq!(part_two(&input), Some(2713310158));
     }
+
+    // Follow one item through a whole round, returning where it ends up and
+    // how many times each monkey inspected it along the way
+    fn item_round(
+        monkeys: &[Monkey],
+        item: (usize, Worry),
+        magic: &Worry,
+    ) -> ((usize, Worry), Vec<u64>) {
+        let mut inspections = vec![0; monkeys.len()];
+        let (mut monkey, mut worry) = item;
+        loop {
+            inspections[monkey] += 1;
+            let throw = monkeys[monkey].inspect_item(worry, 1, Some(magic)).unwrap();
+            let destination = throw.to;
+            worry = throw.worry;
+            // Items thrown backwards wait for the next round
+            if destination < monkey {
+                return ((destination, worry), inspections);
+            }
+            monkey = destination;
+        }
+    }
+
+    #[test]
+    fn test_fast_forward_items() {
+        // Items never interact, so each one can be fast-forwarded on its own
+        let input = aoc::read_file("examples", 11);
+        let monkeys = parse_monkeys(&input).unwrap();
+        let magic = monkeys.iter().map(|m| &m.divisible).product::<Worry>();
+        let mut counts = vec![0; monkeys.len()];
+        for (start, monkey) in monkeys.iter().enumerate() {
+            for item in &monkey.items {
+                let detector = CycleDetector::new((start, item.clone()), |state| {
+                    item_round(&monkeys, state.clone(), &magic).0
+                });
+                assert!(detector.cycle().length < 10_000);
+                for (idx, count) in counts.iter_mut().enumerate() {
+                    *count += detector.accumulate_after(10_000, |state| {
+                        item_round(&monkeys, state.clone(), &magic).1[idx]
+                    });
+                }
+            }
+        }
+        counts.sort();
+        assert_eq!(counts, vec![1938, 47830, 52013, 52166]);
+        assert_eq!(counts[2] * counts[3], 2713310158);
+    }
+
+    #[test]
+    fn test_expressions() {
+        let parse = |s: &str| all_consuming(expr)(s).unwrap().1;
+        let eval = |s: &str, old: u128, modulus: Option<u128>| {
+            parse(s).eval(&old.into(), modulus.map(Worry::from).as_ref())
+        };
+        assert_eq!(eval(" old * 19", 4, None), Ok(76u32.into()));
+        assert_eq!(eval(" old + old", 4, None), Ok(8u32.into()));
+        // Multiplication binds tighter, brackets tighter still
+        assert_eq!(eval("old + 2 * old - 1", 4, None), Ok(11u32.into()));
+        assert_eq!(eval("(old + 2) * old / 5", 4, None), Ok(4u32.into()));
+        assert_eq!(eval("old - 10", 4, Some(7)), Ok(1u32.into()));
+        assert_eq!(
+            eval("old * old", 1 << 40, Some(1_000_003)),
+            Ok(((1u128 << 80) % 1_000_003).into())
+        );
+        // There's no limit without a modulus
+        assert_eq!(
+            eval("old * old", 1 << 100, None),
+            Ok(Worry::from(1u32) << 200)
+        );
+        assert_eq!(
+            eval("old - 10", 4, None),
+            Err("4 - 10 is negative".to_owned())
+        );
+        assert_eq!(
+            eval("old / 2", 4, Some(7)),
+            Err("can't divide modulo 7".to_owned())
+        );
+        assert_eq!(
+            parse("(old + 2) * old / 5").to_string(),
+            "((old + 2) * old) / 5"
+        );
+    }
+
+    #[test]
+    fn test_troop() {
+        let input = aoc::read_file("examples", 11);
+        let mut troop = Troop::new(parse_monkeys(&input).unwrap(), 3);
+        troop.round().unwrap();
+        let holdings = troop
+            .holdings()
+            .iter()
+            .map(|items| items.iter().join(", "))
+            .collect_vec();
+        assert_eq!(
+            holdings,
+            vec!["20, 23, 27, 26", "2080, 25, 167, 207, 401, 1046", "", ""]
+        );
+        assert_eq!(troop.inspections(), vec![2, 4, 3, 5]);
+        let mut troop = Troop::new(parse_monkeys(&input).unwrap(), 1);
+        for _ in 0..20 {
+            troop.round().unwrap();
+        }
+        assert_eq!(troop.inspections(), vec![99, 97, 8, 103]);
+    }
+
+    #[test]
+    fn test_bad_monkeys() {
+        let input = aoc::read_file("examples", 11).replace("monkey 3", "monkey 4");
+        assert_eq!(
+            parse_monkeys(&input).err(),
+            Some("monkey 0 can't throw to monkey 4".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_worry_arithmetic() {
+        let input = "Monkey 0:
+  Starting items: 79
+  Operation: new = old * old
+  Test: divisible by 4
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 98
+  Operation: new = old * old
+  Test: divisible by 6
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+        let monkeys = parse_monkeys(input).unwrap();
+        assert_eq!(Troop::new(monkeys.clone(), 1).modulus, Some(12u32.into()));
+        // Rounding down can't be done modulo anything
+        assert_eq!(Troop::new(monkeys.clone(), 3).modulus, None);
+        let mut dividing = monkeys.clone();
+        dividing[0].operation = all_consuming(expr)("old * old / 2").unwrap().1;
+        assert_eq!(Troop::new(dividing, 1).modulus, None);
+
+        // So levels are kept whole, however big they get
+        let mut troop = Troop::new(monkeys, 3);
+        for _ in 0..5 {
+            troop.round().unwrap();
+        }
+        assert!(troop.holdings()[0][0].bits() > 1000);
+
+        // The example needs more than 128 bits after round 115
+        let input = aoc::read_file("examples", 11);
+        assert_eq!(process(&input, 3, 115), Ok(384375));
+        assert_eq!(process(&input, 3, 116), Ok(391851));
+        assert_eq!(answer(&input, 3, 150), Some(656064));
+    }
+
+    #[test]
+    fn test_narrate() {
+        let input = aoc::read_file("examples", 11);
+        let text = narrate(&input, 3, 1).unwrap();
+        assert!(text.starts_with(
+            "Monkey 0:
+  Monkey inspects an item with a worry level of 79.
+    Worry level is multiplied by 19 to 1501.
+    Monkey gets bored with item. Worry level is divided by 3 to 500.
+    Current worry level is not divisible by 23.
+    Item with worry level 500 is thrown to monkey 3.
+"
+        ));
+        assert!(text.contains(
+            "  Monkey inspects an item with a worry level of 79.
+    Worry level is multiplied by itself to 6241.
+"
+        ));
+        assert!(text.contains("    Worry level increases by 6 to 71.\n"));
+        assert!(text.ends_with(
+            "After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: \nMonkey 3: \n\n"
+        ));
+    }
+
+    #[test]
+    fn test_csv() {
+        let input = aoc::read_file("examples", 11);
+        let table = csv(&input, 3, 20).unwrap();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 21 * 4);
+        assert_eq!(lines[0], "round,monkey,inspected,items");
+        assert_eq!(lines[1], "0,0,0,79 98");
+        assert_eq!(lines[5], "1,0,2,20 23 27 26");
+        assert_eq!(lines[84], "20,3,105,");
+        assert_eq!(lines[81], "20,0,101,10 12 14 26 34");
+    }
 }
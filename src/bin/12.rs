@@ -1,182 +1,385 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Display;
+use std::str::FromStr;
 
-use std::{
-    cmp::Ordering,
-    collections::{HashMap, HashSet},
-};
+use array2d::Array2D;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+type Location = (usize, usize);
 
-type Map = Vec<Vec<u32>>;
-
-#[derive(Debug, Hash, PartialEq, Eq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+/// Height of a map letter, with `S` below `a` and `E` above `z`
+fn letter_to_height(letter: char) -> u32 {
+    match letter {
+        'S' => 0,
+        'E' => 27,
+        _ => letter as u32 - 'a' as u32 + 1,
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct State {
-    row_idx: usize,
-    col_idx: usize,
-    distance: u32,
+#[derive(Debug)]
+struct Heightmap {
+    heights: Array2D<u32>,
+    start: Location,
+    end: Location,
 }
-// The priority queue depends on `Ord`.
-// Explicitly implement the trait so the queue becomes a min-heap
-// instead of a max-heap.
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Notice that the we flip the ordering on costs.
-        // In case of a tie we compare positions - this step is necessary
-        // to make implementations of `PartialEq` and `Ord` consistent.
-        other
-            .distance
-            .cmp(&self.distance)
-            .then_with(|| self.row_idx.cmp(&other.row_idx))
-            .then_with(|| self.col_idx.cmp(&other.col_idx))
+
+impl FromStr for Heightmap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut start, mut end) = (None, None);
+        let mut rows: Vec<Vec<u32>> = Vec::new();
+        for (row_idx, line) in s.lines().enumerate() {
+            let mut row = Vec::new();
+            for (col_idx, c) in line.chars().enumerate() {
+                // The start and end are at the lowest and highest elevations
+                let marker = match c {
+                    'S' => &mut start,
+                    'E' => &mut end,
+                    'a'..='z' => {
+                        row.push(letter_to_height(c));
+                        continue;
+                    }
+                    _ => return Err(format!("line {}: \"{}\" isn't a height", row_idx + 1, c)),
+                };
+                if marker.replace((row_idx, col_idx)).is_some() {
+                    return Err(format!("line {}: there's a second {}", row_idx + 1, c));
+                }
+                row.push(letter_to_height(if c == 'S' { 'a' } else { 'z' }));
+            }
+            if !rows.is_empty() && row.len() != rows[0].len() {
+                return Err(format!(
+                    "line {}: is {} long but line 1 is {}",
+                    row_idx + 1,
+                    row.len(),
+                    rows[0].len()
+                ));
+            }
+            rows.push(row);
+        }
+        Ok(Heightmap {
+            heights: Array2D::from_rows(&rows).unwrap(),
+            start: start.ok_or("there's no start")?,
+            end: end.ok_or("there's no end")?,
+        })
     }
 }
 
-// `PartialOrd` needs to be implemented as well.
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+/// How we're allowed to move around the map and what it costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rules {
+    max_ascent: u32,
+    /// How far we can drop in one step, as far as we like if `None`
+    max_descent: Option<u32>,
+    diagonal: bool,
+    /// Extra cost of a step for each unit of height it climbs or drops
+    height_cost: u32,
 }
 
-fn target(row_idx: usize, col_idx: usize, direction: &Direction) -> (usize, usize) {
-    match direction {
-        Direction::Up => (row_idx - 1, col_idx),
-        Direction::Down => (row_idx + 1, col_idx),
-        Direction::Left => (row_idx, col_idx - 1),
-        Direction::Right => (row_idx, col_idx + 1),
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            max_ascent: 1,
+            max_descent: None,
+            diagonal: false,
+            height_cost: 0,
+        }
     }
 }
 
-fn letter_to_height(letter: char) -> u32 {
-    let height_lookup = ('S'..='S')
-        .chain('a'..='z')
-        .chain('E'..='E')
-        .enumerate()
-        .map(|(idx, c)| (c, idx))
-        .collect::<HashMap<char, usize>>();
-
-    height_lookup[&letter] as u32
+impl Rules {
+    fn allows(&self, from: u32, to: u32) -> bool {
+        if to >= from {
+            to - from <= self.max_ascent
+        } else {
+            self.max_descent.is_none_or(|max| from - to <= max)
+        }
+    }
+
+    fn cost(&self, from: u32, to: u32) -> u32 {
+        1 + self.height_cost * from.abs_diff(to)
+    }
 }
 
-fn input_to_heights(input: &str) -> (Map, Map, usize, usize, usize, usize) {
-    let (mut start_row, mut start_col) = (0, 0);
-    let (mut end_row, mut end_col) = (0, 0);
-    let result = input
-        .lines()
-        .enumerate()
-        .map(|(row_idx, row)| {
-            row.chars()
-                .enumerate()
-                .map(|(col_idx, c)| {
-                    if c == 'S' {
-                        (start_row, start_col) = (row_idx, col_idx);
-                    }
-                    if c == 'E' {
-                        (end_row, end_col) = (row_idx, col_idx);
-                    }
-                    letter_to_height(c)
-                })
-                .collect::<Vec<u32>>()
+impl Heightmap {
+    fn neighbours(
+        &self,
+        (row, col): Location,
+        diagonal: bool,
+    ) -> impl Iterator<Item = Location> + '_ {
+        let offsets: &[(isize, isize)] = match diagonal {
+            false => &[(1, 0), (0, 1), (-1, 0), (0, -1)],
+            true => &[
+                (1, 0),
+                (0, 1),
+                (-1, 0),
+                (0, -1),
+                (1, 1),
+                (1, -1),
+                (-1, 1),
+                (-1, -1),
+            ],
+        };
+        offsets.iter().filter_map(move |&(dr, dc)| {
+            let at = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+            self.heights.get(at.0, at.1).map(|_| at)
         })
-        .collect::<Map>();
+    }
 
-    let mut distance_map: Map = Vec::new();
-    let rows = result.len();
-    let cols = result[0].len();
-    for _ in 0..rows {
-        distance_map.push(vec![std::u32::MAX; cols]);
+    fn can_step(&self, from: Location, to: Location, rules: &Rules) -> bool {
+        rules.allows(self.heights[from], self.heights[to])
     }
-    (result, distance_map, start_row, start_col, end_row, end_col)
-}
 
-/// Given a location return all allowed directions we could move
-fn allowed_directions_for_location(
-    row_idx: usize,
-    col_idx: usize,
-    heights: &Map,
-) -> HashSet<Direction> {
-    let mut result: HashSet<Direction> = HashSet::new();
-    let current_height = heights[row_idx][col_idx];
-    if row_idx > 0 {
-        if (heights[row_idx - 1][col_idx] as i32 - current_height as i32) <= 1 {
-            result.insert(Direction::Up);
+    /// The cheapest path from `from` to the first location that is a `goal`,
+    /// searching backwards over the steps if `reverse` is set. Equal costs
+    /// are explored first come first served, so when every step costs the
+    /// same this is a breadth first search.
+    fn search(
+        &self,
+        from: Location,
+        rules: &Rules,
+        reverse: bool,
+        goal: impl Fn(Location) -> bool,
+    ) -> Option<Vec<Location>> {
+        let (rows, cols) = (self.heights.num_rows(), self.heights.num_columns());
+        let mut best: Array2D<Option<(u32, Location)>> = Array2D::filled_with(None, rows, cols);
+        let mut queue = BinaryHeap::from([Reverse((0, 0, from))]);
+        let mut queued = 0;
+        best[from] = Some((0, from));
+        while let Some(Reverse((cost, _, at))) = queue.pop() {
+            if best[at].is_some_and(|(c, _)| c < cost) {
+                // Already found a cheaper way here
+                continue;
+            }
+            if goal(at) {
+                let mut path = vec![at];
+                while *path.last().unwrap() != from {
+                    path.push(best[*path.last().unwrap()].unwrap().1);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.neighbours(at, rules.diagonal) {
+                let (up, down) = match reverse {
+                    false => (at, next),
+                    true => (next, at),
+                };
+                if !self.can_step(up, down, rules) {
+                    continue;
+                }
+                let next_cost = cost + rules.cost(self.heights[up], self.heights[down]);
+                if best[next].is_none_or(|(c, _)| next_cost < c) {
+                    best[next] = Some((next_cost, at));
+                    queued += 1;
+                    queue.push(Reverse((next_cost, queued, next)));
+                }
+            }
         }
+        None
     }
-    if row_idx < heights.len() - 1 {
-        if (heights[row_idx + 1][col_idx] as i32 - current_height as i32) <= 1 {
-            result.insert(Direction::Down);
-        }
+
+    fn shortest_path(&self, rules: &Rules) -> Option<Vec<Location>> {
+        self.search(self.start, rules, false, |at| at == self.end)
     }
-    if col_idx > 0 {
-        if (heights[row_idx][col_idx - 1] as i32 - current_height as i32) <= 1 {
-            result.insert(Direction::Left);
+
+    /// The shortest path from any of the lowest locations, found by walking
+    /// down from the end
+    fn shortest_hike(&self, rules: &Rules) -> Option<Vec<Location>> {
+        let lowest = letter_to_height('a');
+        let mut path = self.search(self.end, rules, true, |at| self.heights[at] == lowest)?;
+        path.reverse();
+        Some(path)
+    }
+
+    fn cost(&self, path: &[Location], rules: &Rules) -> u32 {
+        path.windows(2)
+            .map(|step| rules.cost(self.heights[step[0]], self.heights[step[1]]))
+            .sum()
+    }
+
+    /// Draw a path the way the puzzle does, with an arrow on each location
+    /// pointing to the next one
+    fn render(&self, path: &[Location]) -> String {
+        let (rows, cols) = (self.heights.num_rows(), self.heights.num_columns());
+        let mut grid = Array2D::filled_with('.', rows, cols);
+        for step in path.windows(2) {
+            let ((r1, c1), (r2, c2)) = (step[0], step[1]);
+            grid[step[0]] = match (r2 as isize - r1 as isize, c2 as isize - c1 as isize) {
+                (-1, 0) => '^',
+                (1, 0) => 'v',
+                (0, -1) => '<',
+                (0, 1) => '>',
+                (-1, -1) => '↖',
+                (-1, 1) => '↗',
+                (1, -1) => '↙',
+                _ => '↘',
+            };
         }
+        if let Some(&last) = path.last() {
+            grid[last] = 'E';
+        }
+        grid.rows_iter()
+            .map(|row| row.collect::<String>() + "\n")
+            .collect()
     }
-    if col_idx < heights[0].len() - 1 {
-        if (heights[row_idx][col_idx + 1] as i32 - current_height as i32) <= 1 {
-            result.insert(Direction::Right);
+}
+
+impl Display for Heightmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (row_idx, row) in self.heights.rows_iter().enumerate() {
+            for (col_idx, &height) in row.enumerate() {
+                let letter = match (row_idx, col_idx) {
+                    at if at == self.start => 'S',
+                    at if at == self.end => 'E',
+                    _ => char::from(b'a' + (height - letter_to_height('a')) as u8),
+                };
+                write!(f, "{}", letter)?;
+            }
+            writeln!(f)?;
         }
+        Ok(())
     }
-    result
 }
 
-/// From a given location, consider adjacent locations and update their distances
-fn process_location(
-    distance: u32,
-    row_idx: usize,
-    col_idx: usize,
-    heights: &Map,
-    distances: &mut Map,
-    reset_at_a: bool,
-) {
-    // Update our distance
-    let mut corrected_dist = distance;
-    if reset_at_a && heights[row_idx][col_idx] == 1 {
-        // Reset to 0 as we are on the ground
-        corrected_dist = 0;
-    }
-    distances[row_idx][col_idx] = corrected_dist;
-    let allowed_directions = allowed_directions_for_location(row_idx, col_idx, heights);
-    for direction in allowed_directions.iter() {
-        let (target_row, target_col) = target(row_idx, col_idx, direction);
-        let target_distance = corrected_dist + 1;
-        if distances[target_row][target_col] > target_distance {
-            process_location(
-                target_distance,
-                target_row,
-                target_col,
-                heights,
-                distances,
-                reset_at_a,
-            );
+/// A random map that can be climbed, along with the number of steps in its
+/// shortest path under the puzzle's rules. Neighbouring heights never differ
+/// by more than one along a staircase from `S` straight to `E`, so the
+/// shortest path is as many steps as it is across and down. Elsewhere there
+/// are peaks and pits to get in the way.
+fn generate(rows: usize, cols: usize, seed: u64) -> Result<(Heightmap, usize), String> {
+    let top = (letter_to_height('z') - letter_to_height('a')) as usize;
+    if rows + cols < top + 2 {
+        return Err(format!(
+            "a {}x{} map is too small to climb from a to z",
+            rows, cols
+        ));
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let distance = |(r1, c1): Location, (r2, c2): Location| r1.abs_diff(r2) + c1.abs_diff(c2);
+    let random = |rng: &mut StdRng| (rng.gen_range(0..rows), rng.gen_range(0..cols));
+    let (start, end) = loop {
+        let (start, end) = (random(&mut rng), random(&mut rng));
+        if distance(start, end) >= top {
+            break (start, end);
+        }
+    };
+
+    // Every peak is low enough to leave the start at the bottom, and the
+    // ground falls away from them by at most one each step
+    let mut peaks = vec![(end, top)];
+    for _ in 0..rng.gen_range(1..=4) {
+        let at = random(&mut rng);
+        peaks.push((at, rng.gen_range(0..=distance(start, at).min(top))));
+    }
+    let mut heights = Array2D::filled_with(0, rows, cols);
+    for row in 0..rows {
+        for col in 0..cols {
+            let height = peaks
+                .iter()
+                .map(|&(at, height)| height.saturating_sub(distance((row, col), at)))
+                .max()
+                .unwrap();
+            heights[(row, col)] = letter_to_height('a') + height as u32;
         }
     }
+
+    // Dig pits anywhere off a staircase that heads straight for the end
+    let mut staircase = Array2D::filled_with(false, rows, cols);
+    staircase[start] = true;
+    let (mut row, mut col) = start;
+    while (row, col) != end {
+        let go_down = col == end.1 || (row != end.0 && rng.gen_bool(0.5));
+        if go_down {
+            row = if end.0 > row { row + 1 } else { row - 1 };
+        } else {
+            col = if end.1 > col { col + 1 } else { col - 1 };
+        }
+        staircase[(row, col)] = true;
+    }
+    for row in 0..rows {
+        for col in 0..cols {
+            if !staircase[(row, col)] && rng.gen_bool(0.2) {
+                let height = heights[(row, col)];
+                heights[(row, col)] = rng.gen_range(letter_to_height('a')..=height);
+            }
+        }
+    }
+    let map = Heightmap {
+        heights,
+        start,
+        end,
+    };
+    Ok((map, distance(start, end)))
+}
+
+fn steps(path: Option<Vec<Location>>) -> Option<u32> {
+    path.map(|path| path.len() as u32 - 1)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let (heights, mut distances, start_row, start_col, end_row, end_col) = input_to_heights(input);
-    process_location(0, start_row, start_col, &heights, &mut distances, false);
-    let result = distances[end_row][end_col];
-    Some(result)
+    let map = input.parse::<Heightmap>().unwrap();
+    steps(map.shortest_path(&Rules::default()))
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let (heights, mut distances, start_row, start_col, end_row, end_col) = input_to_heights(input);
-    process_location(0, start_row, start_col, &heights, &mut distances, true);
-    let result = distances[end_row][end_col];
-    Some(result)
+    let map = input.parse::<Heightmap>().unwrap();
+    steps(map.shortest_hike(&Rules::default()))
+}
+
+fn print_paths(map: &Heightmap, rules: &Rules) {
+    for (name, path) in [
+        ("start", map.shortest_path(rules)),
+        ("hike", map.shortest_hike(rules)),
+    ] {
+        match path {
+            Some(path) => println!(
+                "{} ({} steps costing {}):\n{}",
+                name,
+                path.len() - 1,
+                map.cost(&path, rules),
+                map.render(&path)
+            ),
+            None => println!("{}: the end can't be reached", name),
+        }
+    }
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let rules = Rules {
+        max_ascent: args
+            .opt_value_from_str("--max-ascent")
+            .unwrap()
+            .unwrap_or(1),
+        max_descent: args.opt_value_from_str("--max-descent").unwrap(),
+        diagonal: args.contains("--diagonal"),
+        height_cost: args
+            .opt_value_from_str("--height-cost")
+            .unwrap()
+            .unwrap_or(0),
+    };
+    // Make up a map rather than reading the input
+    if let Some(size) = args.opt_value_from_str::<_, String>("--generate").unwrap() {
+        let (rows, cols) = size.split_once('x').expect("sizes look like 40x120");
+        let seed = args.opt_value_from_str("--seed").unwrap().unwrap_or(0);
+        match generate(rows.parse().unwrap(), cols.parse().unwrap(), seed) {
+            Ok((map, length)) => {
+                println!("{}\nshortest path is {} steps", map, length);
+                print_paths(&map, &rules);
+            }
+            Err(e) => println!("{}", e),
+        }
+        return;
+    }
+
     let input = &aoc::read_file("inputs", 12);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+    if args.contains("--path") {
+        print_paths(&input.parse::<Heightmap>().unwrap(), &rules);
+    }
 }
 
 #[cfg(test)]
@@ -201,4 +404,113 @@ mod tests {
         assert_eq!(letter_to_height('a'), 1);
         assert_eq!(letter_to_height('g'), 7);
     }
+
+    #[test]
+    fn test_render() {
+        let map = aoc::read_file("examples", 12).parse::<Heightmap>().unwrap();
+        let rules = Rules::default();
+        let path = map.shortest_path(&rules).unwrap();
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(2, 5)));
+        for step in path.windows(2) {
+            assert!(map.neighbours(step[0], false).any(|at| at == step[1]));
+            assert!(map.can_step(step[0], step[1], &rules));
+        }
+        // One of several shortest paths, the puzzle draws another
+        assert_eq!(
+            map.render(&path),
+            "v..v<<<<\n>v.vv<<^\n.v.v>E^^\n.>v>>>^^\n..>>>>>^\n"
+        );
+        let hike = map.shortest_hike(&rules).unwrap();
+        assert_eq!(map.heights[hike[0]], letter_to_height('a'));
+        assert_eq!(hike.last(), Some(&(2, 5)));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        // The z is walled off by cliffs
+        let input = "SbcdE\nabxzz";
+        assert_eq!(part_one(input), None);
+        assert_eq!(part_two(input), None);
+        assert_eq!(
+            part_one("Sbcdefghijklmnopqrstuvwxyz\nzzzzzzzzzzzzzzzzzzzzzzzzzE"),
+            Some(26)
+        );
+    }
+
+    #[test]
+    fn test_bad_map() {
+        assert_eq!(
+            "Sab\nabE\nab".parse::<Heightmap>().err(),
+            Some("line 3: is 2 long but line 1 is 3".to_owned())
+        );
+        assert_eq!(
+            "SaS\nabE".parse::<Heightmap>().err(),
+            Some("line 1: there's a second S".to_owned())
+        );
+        assert_eq!(
+            "Sa1\nabE".parse::<Heightmap>().err(),
+            Some("line 1: \"1\" isn't a height".to_owned())
+        );
+        assert_eq!(
+            "Sab".parse::<Heightmap>().err(),
+            Some("there's no end".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let map = aoc::read_file("examples", 12).parse::<Heightmap>().unwrap();
+        let diagonal = Rules {
+            diagonal: true,
+            ..Rules::default()
+        };
+        let path = map.shortest_path(&diagonal).unwrap();
+        assert!(path.len() - 1 < 31);
+        assert_eq!(map.cost(&path, &diagonal), path.len() as u32 - 1);
+        // Anything can be climbed in one step
+        let giant = Rules {
+            max_ascent: 26,
+            ..Rules::default()
+        };
+        assert_eq!(steps(map.shortest_path(&giant)), Some(7));
+
+        // Without going down, the way over the peak is a dead end
+        let map = "SzaE\nbbbb".parse::<Heightmap>().unwrap();
+        let careful = Rules {
+            max_descent: Some(0),
+            ..giant
+        };
+        assert_eq!(steps(map.shortest_path(&giant)), Some(3));
+        assert_eq!(steps(map.shortest_path(&careful)), Some(5));
+        assert!(!careful.allows(3, 2));
+
+        // Climbing costs extra, so the cheapest route goes round the peak
+        let map = "SmaE\nbbbb".parse::<Heightmap>().unwrap();
+        let weighted = Rules {
+            height_cost: 1,
+            ..giant
+        };
+        let path = map.shortest_path(&weighted).unwrap();
+        assert_eq!((path.len() - 1, map.cost(&path, &weighted)), (5, 30));
+        assert_eq!(steps(map.shortest_path(&giant)), Some(3));
+    }
+
+    #[test]
+    fn test_generate() {
+        for (rows, cols, seed) in [(13, 14, 0), (20, 30, 1), (41, 97, 2), (5, 60, 3)] {
+            let (map, length) = generate(rows, cols, seed).unwrap();
+            assert_eq!(map.heights.num_rows(), rows);
+            assert_eq!(map.heights.num_columns(), cols);
+            let path = map.shortest_path(&Rules::default()).unwrap();
+            assert_eq!(path.len() - 1, length);
+            let text = map.to_string();
+            assert_eq!(text.parse::<Heightmap>().unwrap().to_string(), text);
+            assert_eq!(part_one(&text), Some(length as u32));
+        }
+        assert_eq!(
+            generate(10, 10, 0).err(),
+            Some("a 10x10 map is too small to climb from a to z".to_owned())
+        );
+    }
 }
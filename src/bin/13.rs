@@ -1,65 +1,65 @@
-#![feature(iter_intersperse)]
-use std::{cmp, fmt::Display};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::slice;
 
+use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{newline, u32 as nom32};
+use nom::character::complete::u32 as nom32;
+use nom::combinator::all_consuming;
 use nom::multi::separated_list0;
-use nom::sequence::{delimited, separated_pair, tuple};
+use nom::sequence::delimited;
 use nom::IResult;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Eq)]
+/// A packet, or part of one. Packets are JSON arrays of numbers and arrays,
+/// and serialize as exactly that.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
 enum Element {
     List(Vec<Element>),
     Value(u32),
 }
 
-impl Ord for Element {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
+/// The order packets should be in. A number compares as though it were a
+/// list holding just that number.
+#[derive(Debug, Default, Clone, Copy)]
+struct PacketOrder;
+
+impl PacketOrder {
+    fn compare(&self, left: &Element, right: &Element) -> Ordering {
         use Element::*;
-        match (self, other) {
-            (List(l), List(r)) => l.cmp(r),
-            (List(l), Value(r)) => l.cmp(&vec![Value(*r)]),
-            (Value(l), List(r)) => vec![Value(*l)].cmp(r),
+        match (left, right) {
+            (List(l), List(r)) => self.compare_lists(l, r),
+            (List(l), Value(_)) => self.compare_lists(l, slice::from_ref(right)),
+            (Value(_), List(r)) => self.compare_lists(slice::from_ref(left), r),
             (Value(l), Value(r)) => l.cmp(r),
         }
     }
-}
 
-impl PartialOrd for Element {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
+    fn compare_lists(&self, left: &[Element], right: &[Element]) -> Ordering {
+        left.iter()
+            .zip(right)
+            .map(|(l, r)| self.compare(l, r))
+            .find(|&order| order != Ordering::Equal)
+            .unwrap_or_else(|| left.len().cmp(&right.len()))
     }
-}
 
-impl PartialEq for Element {
-    fn eq(&self, other: &Self) -> bool {
-        use Element::*;
-        match (self, other) {
-            (List(l), List(r)) => l == r,
-            (List(l), Value(r)) => l == &vec![Value(*r)],
-            (Value(l), List(r)) => r == &vec![Value(*l)],
-            (Value(l), Value(r)) => l == r,
-        }
+    fn in_order(&self, left: &Element, right: &Element) -> bool {
+        self.compare(left, right) == Ordering::Less
+    }
+
+    fn sort(&self, packets: &mut [Element]) {
+        packets.sort_by(|l, r| self.compare(l, r));
     }
 }
 
 impl Display for Element {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Element::List(list) => format!(
-                    "[{}]",
-                    list.iter()
-                        .map(|v| v.to_string())
-                        .intersperse(",".to_string())
-                        .collect::<String>()
-                ),
-                Element::Value(num) => num.to_string(),
-            }
-        )
+        match self {
+            Element::List(list) => write!(f, "[{}]", list.iter().join(",")),
+            Element::Value(num) => write!(f, "{}", num),
+        }
     }
 }
 
@@ -77,56 +77,92 @@ fn parse_element(input: &str) -> IResult<&str, Element> {
     Ok((input, Element::List(data)))
 }
 
-fn parse_pair(input: &str) -> IResult<&str, (Element, Element)> {
-    let (input, (left, _, right)) = tuple((sub_list, newline, sub_list))(input)?;
-    Ok((input, (left, right)))
-}
-
-fn parse_all_input(input: &str) -> IResult<&str, Vec<(Element, Element)>> {
-    separated_list0(separated_pair(newline, tag(""), newline), parse_pair)(input)
+/// Every pair of packets, each pair separated from the next by a blank line
+fn parse_pairs(input: &str) -> Result<Vec<(Element, Element)>, String> {
+    let mut pairs = Vec::new();
+    let mut lines = input.lines().enumerate().peekable();
+    while lines.peek().is_some() {
+        let mut pair = Vec::new();
+        for (idx, line) in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (_, packet) = all_consuming(sub_list)(line)
+                .map_err(|_| format!("line {}: \"{}\" isn't a packet", idx + 1, line))?;
+            pair.push((idx, packet));
+        }
+        match <[_; 2]>::try_from(pair) {
+            Ok([(_, left), (_, right)]) => pairs.push((left, right)),
+            // Extra blank lines don't make a pair
+            Err(pair) if pair.is_empty() => {}
+            Err(pair) => {
+                let line = pair[0].0 + 1;
+                return Err(format!(
+                    "line {}: a pair of packets has {}",
+                    line,
+                    pair.len()
+                ));
+            }
+        }
+    }
+    Ok(pairs)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    use std::cmp::Ordering::*;
-    let (_, elements) = parse_all_input(input).unwrap();
-    let result = elements
-        .into_iter()
+    let pairs = parse_pairs(input).unwrap();
+    let result = pairs
+        .iter()
         .enumerate()
-        .filter_map(|(idx, pair)| match pair.0.cmp(&pair.1) {
-            Less => Some(idx as u32 + 1),
-            _ => None,
-        })
+        .filter(|(_, (left, right))| PacketOrder.in_order(left, right))
+        .map(|(idx, _)| idx as u32 + 1)
         .sum::<u32>();
     Some(result)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    use Element::*;
-    let mut elements = input
-        .lines()
-        .filter_map(|line| match sub_list(line) {
-            Ok((_, e)) => Some(e),
-            Err(_) => None,
-        })
-        .collect::<Vec<Element>>();
-    let divider_1 = List(vec![Value(2)]);
-    let divider_2 = List(vec![Value(6)]);
-    elements.push(divider_1.clone());
-    elements.push(divider_2.clone());
-
-    // let sorted_elements = sort_elements(elements);
-    elements.sort();
+/// All the packets and the dividers, in order
+fn sorted_packets(input: &str, dividers: &[Element]) -> Vec<Element> {
+    let mut packets = parse_pairs(input)
+        .unwrap()
+        .into_iter()
+        .flat_map(|(left, right)| [left, right])
+        .chain(dividers.iter().cloned())
+        .collect_vec();
+    PacketOrder.sort(&mut packets);
+    packets
+}
 
-    let idx_1 = elements.iter().position(|e| e == &divider_1).unwrap() as u32;
-    let idx_2 = elements.iter().position(|e| e == &divider_2).unwrap() as u32;
+fn dividers() -> [Element; 2] {
+    use Element::*;
+    [
+        List(vec![List(vec![Value(2)])]),
+        List(vec![List(vec![Value(6)])]),
+    ]
+}
 
-    Some((idx_1 + 1) * (idx_2 + 1))
+pub fn part_two(input: &str) -> Option<u32> {
+    let dividers = dividers();
+    let packets = sorted_packets(input, &dividers);
+    dividers
+        .iter()
+        .map(|divider| {
+            packets
+                .iter()
+                .position(|p| p == divider)
+                .map(|idx| idx as u32 + 1)
+        })
+        .product()
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 13);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--json") {
+        let packets = sorted_packets(input, &dividers());
+        println!("{}", serde_json::to_string_pretty(&packets).unwrap());
+    }
 }
 
 #[cfg(test)]
@@ -148,7 +184,60 @@ mod tests {
     #[test]
     fn test_parse_input() {
         let input = aoc::read_file("examples", 13);
-        let (_, result) = parse_all_input(&input).unwrap();
+        let result = parse_pairs(&input).unwrap();
         assert_eq!(result.len(), 8);
     }
+
+    #[test]
+    fn test_json() {
+        let input = aoc::read_file("examples", 13);
+        for line in input.lines().filter(|line| !line.is_empty()) {
+            let (_, packet) = sub_list(line).unwrap();
+            assert_eq!(serde_json::from_str::<Element>(line).unwrap(), packet);
+            assert_eq!(serde_json::to_string(&packet).unwrap(), line);
+            assert_eq!(packet.to_string(), line);
+        }
+        let packet: Element = serde_json::from_str("[ [1, []], 2 ]").unwrap();
+        assert_eq!(packet.to_string(), "[[1,[]],2]");
+        assert!(serde_json::from_str::<Element>("[1, -2]").is_err());
+    }
+
+    #[test]
+    fn test_packet_order() {
+        let parse = |s: &str| serde_json::from_str::<Element>(s).unwrap();
+        // A number is compared as a list of just that number, but isn't one
+        assert_eq!(
+            PacketOrder.compare(&parse("[[2]]"), &parse("[2]")),
+            Ordering::Equal
+        );
+        assert_ne!(parse("[[2]]"), parse("[2]"));
+        assert!(PacketOrder.in_order(&parse("[[]]"), &parse("[[[]]]")));
+        assert!(!PacketOrder.in_order(
+            &parse("[1,[2,[3,[4,[5,6,7]]]],8,9]"),
+            &parse("[1,[2,[3,[4,[5,6,0]]]],8,9]")
+        ));
+        let input = aoc::read_file("examples", 13);
+        let sorted = sorted_packets(&input, &dividers());
+        assert_eq!(sorted.len(), 18);
+        assert_eq!(sorted[0].to_string(), "[]");
+        assert_eq!(sorted[9].to_string(), "[[2]]");
+        assert_eq!(sorted[17].to_string(), "[9]");
+    }
+
+    #[test]
+    fn test_strict_parsing() {
+        assert_eq!(
+            parse_pairs("[1]\n[2]\n\n[1,x]\n[3]").err(),
+            Some("line 4: \"[1,x]\" isn't a packet".to_owned())
+        );
+        assert_eq!(
+            parse_pairs("[1]\n[2]\n\n[3]\n").err(),
+            Some("line 4: a pair of packets has 1".to_owned())
+        );
+        assert_eq!(
+            parse_pairs("[1]\n[2]\n[3]").err(),
+            Some("line 1: a pair of packets has 3".to_owned())
+        );
+        assert_eq!(parse_pairs("[1]\n[2]\n\n\n[3]\n[4]\n\n").unwrap().len(), 2);
+    }
 }
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use aoc::helpers::visualize::{Pixel, Visualizer};
 use array2d::Array2D;
 use itertools::Itertools;
 use nom::bytes::complete::tag;
@@ -16,10 +17,24 @@ enum Content {
 
 impl Display for Content {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.glyph())
+    }
+}
+
+impl Pixel for Content {
+    fn glyph(&self) -> char {
         match self {
-            Content::Sand => write!(f, "o"),
-            Content::Rock => write!(f, "#"),
-            Content::Void => write!(f, "."),
+            Content::Sand => 'o',
+            Content::Rock => '#',
+            Content::Void => '.',
+        }
+    }
+
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            Content::Sand => [0xe8, 0xc1, 0x6d],
+            Content::Rock => [0x6b, 0x6b, 0x6b],
+            Content::Void => [0x0f, 0x0f, 0x23],
         }
     }
 }
@@ -40,7 +55,7 @@ struct IndexMapper {
     y_min: i32,
 }
 impl IndexMapper {
-    fn to_index(self: &Self, point: &Point) -> (usize, usize) {
+    fn to_index(&self, point: &Point) -> (usize, usize) {
         (
             // x => col, y=> row so need to swap order
             (point.y - self.y_min) as usize,
@@ -87,7 +102,7 @@ fn find_min_max(paths: &Vec<Vec<Point>>) -> (Point, Point) {
 }
 
 fn input_to_grid(input: &str) -> (Array2D<Content>, IndexMapper) {
-    let (_, paths) = path_list(&input).unwrap();
+    let (_, paths) = path_list(input).unwrap();
     let (min, max) = find_min_max(&paths);
     let mapper = IndexMapper {
         x_min: min.x,
@@ -111,7 +126,7 @@ fn input_to_grid(input: &str) -> (Array2D<Content>, IndexMapper) {
 }
 
 fn input_to_grid_with_floor(input: &str) -> (Array2D<Content>, IndexMapper) {
-    let (_, paths) = path_list(&input).unwrap();
+    let (_, paths) = path_list(input).unwrap();
     let (mut min, mut max) = find_min_max(&paths);
     let floor_depth = max.y + 2;
     max.y = floor_depth;
@@ -146,15 +161,6 @@ fn input_to_grid_with_floor(input: &str) -> (Array2D<Content>, IndexMapper) {
     (grid, mapper)
 }
 
-fn print_grid(grid: &Array2D<Content>) {
-    let s = grid
-        .as_rows()
-        .into_iter()
-        .map(|row| row.into_iter().map(|e| e).join(""))
-        .join("\n");
-    println!("{}", s);
-}
-
 #[derive(PartialEq, Eq)]
 enum SandOutcome {
     Stuck,
@@ -163,7 +169,7 @@ enum SandOutcome {
 
 fn future_locations(point: &Point) -> Vec<Point> {
     let y = point.y + 1;
-    vec![point.x, point.x - 1, point.x + 1]
+    [point.x, point.x - 1, point.x + 1]
         .iter()
         .map(|&x| Point { x, y })
         .collect()
@@ -182,15 +188,12 @@ fn simulate_grain(
         '_inner: for l in future_locations(&location) {
             let (row, col) = mapper.to_index(&l);
             match grid.get(row, col) {
-                Some(e) => match e {
-                    Content::Void => {
-                        location.x = l.x;
-                        location.y = l.y;
-                        continue 'outer; // No need to check other locations, we can move
-                    }
-
-                    _ => {} // blocked here so just continue checking
-                },
+                Some(Content::Void) => {
+                    location.x = l.x;
+                    location.y = l.y;
+                    continue 'outer; // No need to check other locations, we can move
+                }
+                Some(_) => {} // blocked here so just continue checking
                 None => {
                     // We can move off the end of the grid so the sand grain must be free
                     return SandOutcome::Free;
@@ -213,29 +216,44 @@ pub fn part_one(input: &str) -> Option<u32> {
     Some(count)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let (mut grid, mapper) = input_to_grid_with_floor(input);
+/// Pour sand until it blocks the ingress, calling `on_grain` after each grain comes to rest
+fn fill_to_ingress(
+    grid: &mut Array2D<Content>,
+    mapper: &IndexMapper,
+    mut on_grain: impl FnMut(&Array2D<Content>),
+) -> u32 {
     let ingress = Point { x: 500, y: 0 };
 
     while grid[mapper.to_index(&ingress)] != Content::Sand {
-        print!("{}[2J", 27 as char);
-        print_grid(&grid);
-        simulate_grain(&mut grid, &mapper, &ingress);
+        simulate_grain(grid, mapper, &ingress);
+        on_grain(grid);
     }
-    let grains = grid
-        .elements_column_major_iter()
+    grid.elements_column_major_iter()
         .map(|e| match e {
             Content::Sand => 1,
             _ => 0,
         })
-        .sum::<u32>();
-    Some(grains)
+        .sum::<u32>()
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let (mut grid, mapper) = input_to_grid_with_floor(input);
+    Some(fill_to_ingress(&mut grid, &mapper, |_| {}))
+}
+
+fn visualize(input: &str, visualizer: &mut Visualizer) {
+    let (mut grid, mapper) = input_to_grid_with_floor(input);
+    visualizer.frame(&grid);
+    fill_to_ingress(&mut grid, &mapper, |grid| visualizer.frame(grid));
 }
 
 fn main() {
     let input = &aoc::read_file("inputs", 14);
     aoc::solve!(1, part_one, input);
     aoc::solve!(2, part_two, input);
+    if let Some(mut visualizer) = Visualizer::from_args() {
+        visualize(input, &mut visualizer);
+    }
 }
 
 #[cfg(test)]
@@ -280,6 +298,6 @@ mod tests {
             y_min: min.y,
         };
         assert_eq!(mapper.to_index(&min), (0, 0));
-        assert_eq!(mapper.to_index(&Point { x: 500, y: 0 }), (6, 0));
+        assert_eq!(mapper.to_index(&Point { x: 500, y: 0 }), (0, 6));
     }
 }
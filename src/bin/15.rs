@@ -13,7 +13,7 @@ struct Sensor {
 }
 
 impl Sensor {
-    fn x_range_at_row(self: &Self, row: i32) -> Option<Range> {
+    fn x_range_at_row(&self, row: i32) -> Option<Range> {
         let m = manhattan((self.x, self.y), (self.b_x, self.b_y));
         let dy = (row - self.y).abs();
         if dy > m {
@@ -35,7 +35,7 @@ struct Range {
 }
 
 impl Range {
-    fn overlapping(self: &Self, other: &Range) -> bool {
+    fn overlapping(&self, other: &Range) -> bool {
         (other.start >= self.start && other.start <= self.end)
             || (other.end >= self.start && other.end <= self.end)
     }
@@ -51,12 +51,12 @@ struct BoundedRangeList {
 impl BoundedRangeList {
     fn new(min: Option<i32>, max: Option<i32>) -> BoundedRangeList {
         BoundedRangeList {
-            min: min,
-            max: max,
+            min,
+            max,
             ranges: Vec::<Range>::new(),
         }
     }
-    fn insert(self: &mut Self, range: &mut Range) -> () {
+    fn insert(&mut self, range: &mut Range) {
         // Set range within bounds
         if let Some(min) = self.min {
             if range.end < min {
@@ -80,18 +80,18 @@ impl BoundedRangeList {
                 range.end = range.end.max(r.end);
             } else {
                 if range.end < r.start {
-                    new_ranges.push(range.clone());
+                    new_ranges.push(*range);
                     have_pushed = true;
                 }
-                new_ranges.push(r.clone());
+                new_ranges.push(*r);
             }
         }
         if !have_pushed {
-            new_ranges.push(range.clone());
+            new_ranges.push(*range);
         }
         self.ranges = new_ranges;
     }
-    fn len(self: &Self) -> u32 {
+    fn len(&self) -> u32 {
         self.ranges
             .iter()
             .map(|r| (r.end - r.start + 1) as u32)
@@ -119,7 +119,7 @@ fn input_to_sensors(input: &str) -> IResult<&str, Vec<Sensor>> {
     separated_list1(newline, line_to_beacon)(input)
 }
 
-fn excluded_from_row(sensors: &Vec<Sensor>, row: i32) -> u32 {
+fn excluded_from_row(sensors: &[Sensor], row: i32) -> u32 {
     let mut ranges = BoundedRangeList::new(None, None);
     let mut beacons_on_row = HashSet::<i32>::new();
     for s in sensors {
@@ -134,7 +134,7 @@ fn excluded_from_row(sensors: &Vec<Sensor>, row: i32) -> u32 {
     ranges.len() - beacons_on_row.len() as u32
 }
 
-fn row_has_missing_spot(sensors: &Vec<Sensor>, row: i32, min: i32, max: i32) -> Option<i32> {
+fn row_has_missing_spot(sensors: &[Sensor], row: i32, min: i32, max: i32) -> Option<i32> {
     // Check if the row has exactly 1 unknown item on it
     // if so ,return the idx of that missing space
     let mut ranges = BoundedRangeList::new(Some(min), Some(max));
@@ -161,16 +161,22 @@ pub fn part_one(input: &str) -> Option<u32> {
     Some(excluded_from_row(&sensors, row))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+/// Find the only spot within `0..=max` in both directions that no sensor rules out
+fn distress_beacon(sensors: &[Sensor], max: i32) -> Option<(i32, i32)> {
+    (0..=max).find_map(|row| {
+        let col = row_has_missing_spot(sensors, row, 0, max)?;
+        aoc::debug!("found a gap at x={}, y={}", col, row);
+        Some((col, row))
+    })
+}
+
+fn tuning_frequency((col, row): (i32, i32)) -> u64 {
+    col as u64 * 4000000 + row as u64
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
     let (_, sensors) = input_to_sensors(input).unwrap();
-    let max = 4000000;
-    let scale = 4000000;
-    for row in 0..=max {
-        if let Some(col) = row_has_missing_spot(&sensors, row, 0, max) {
-            dbg!(col as u128 * scale as u128 + row as u128);
-        }
-    }
-    None
+    distress_beacon(&sensors, 4000000).map(tuning_frequency)
 }
 
 fn main() {
@@ -197,15 +203,9 @@ mod tests {
     fn test_part_two() {
         let input = aoc::read_file("examples", 15);
         let (_, sensors) = input_to_sensors(&input).unwrap();
-        let max = 20;
-        let scale = 4000000;
-        let mut result: Option<u32> = None;
-        for row in 0..=max {
-            if let Some(col) = row_has_missing_spot(&sensors, row, 0, max) {
-                result = Some((col * scale + row) as u32);
-            }
-        }
-        assert_eq!(result, Some(56000011));
+        let beacon = distress_beacon(&sensors, 20);
+        assert_eq!(beacon, Some((14, 11)));
+        assert_eq!(beacon.map(tuning_frequency), Some(56000011));
     }
 
     #[test]
@@ -216,11 +216,11 @@ mod tests {
             b_x: 2,
             b_y: 10,
         };
-        assert_eq!(s.x_range_at_row(-1), Some(Range { start: 7, end: 10 }));
-        assert_eq!(s.x_range_at_row(-2), Some(Range { start: 8, end: 9 }));
+        assert_eq!(s.x_range_at_row(-1), Some(Range { start: 7, end: 9 }));
+        assert_eq!(s.x_range_at_row(-2), Some(Range { start: 8, end: 8 }));
         assert_eq!(s.x_range_at_row(-3), None);
         assert_eq!(s.x_range_at_row(-4), None);
-        assert_eq!(s.x_range_at_row(16), Some(Range { start: 8, end: 9 }));
+        assert_eq!(s.x_range_at_row(16), Some(Range { start: 8, end: 8 }));
         assert_eq!(s.x_range_at_row(17), None);
     }
     #[test]
@@ -2,3 +2,7 @@
  * Use this file if you want to extract helpers from your solutions.
  * Example import from this file: `use aoc::helpers::example_fn;`.
  */
+pub mod cycle;
+pub mod memo;
+pub mod ocr;
+pub mod visualize;
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A cycle in a sequence of states: the state at step `start` is seen again
+/// `length` steps later, and the sequence repeats from there on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Return the earliest step that is in the same state as step `n`
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+/// Find the cycle reached by repeatedly applying `step` to `initial`,
+/// using Brent's algorithm so that only a couple of states are held at once
+pub fn brent<S, F>(initial: S, mut step: F) -> Cycle
+where
+    S: Clone + Eq,
+    F: FnMut(&S) -> S,
+{
+    // Find the cycle length by racing a hare ahead of a tortoise that
+    // teleports to the hare at every power of two
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    // Then find the start by walking two pointers `length` apart
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, length }
+}
+
+/// Records every state of a simulation up to its first repeat so that any
+/// later state, or a metric summed over many steps, can be found by
+/// skipping whole periods instead of stepping through them
+#[derive(Debug)]
+pub struct CycleDetector<S> {
+    history: Vec<S>,
+    cycle: Cycle,
+}
+
+impl<S> CycleDetector<S>
+where
+    S: Clone + Eq + Hash,
+{
+    pub fn new<F>(initial: S, mut step: F) -> Self
+    where
+        F: FnMut(&S) -> S,
+    {
+        let mut seen = HashMap::<S, usize>::new();
+        let mut history = Vec::<S>::new();
+        let mut state = initial;
+        loop {
+            if let Some(&start) = seen.get(&state) {
+                let cycle = Cycle {
+                    start,
+                    length: history.len() - start,
+                };
+                return Self { history, cycle };
+            }
+            let next = step(&state);
+            seen.insert(state.clone(), history.len());
+            history.push(state);
+            state = next;
+        }
+    }
+
+    pub fn cycle(&self) -> Cycle {
+        self.cycle
+    }
+
+    /// Return the state after `n` steps
+    pub fn state_after(&self, n: usize) -> &S {
+        &self.history[self.cycle.equivalent_step(n)]
+    }
+
+    /// Return the sum of `metric` over the states at steps `0..n`, i.e. the
+    /// total accumulated by the first `n` transitions
+    pub fn accumulate_after<F>(&self, n: usize, metric: F) -> u64
+    where
+        F: Fn(&S) -> u64,
+    {
+        let sum = |from: usize, to: usize| self.history[from..to].iter().map(&metric).sum::<u64>();
+        let Cycle { start, length } = self.cycle;
+        if n <= self.history.len() {
+            return sum(0, n);
+        }
+        let periods = ((n - start) / length) as u64;
+        let remainder = (n - start) % length;
+        sum(0, start) + periods * sum(start, start + length) + sum(start, start + remainder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(x: &u64) -> u64 {
+        (x * x + 1) % 255
+    }
+
+    #[test]
+    fn test_brent_matches_detector() {
+        for initial in 0..20 {
+            let detector = CycleDetector::new(initial, step);
+            assert_eq!(brent(initial, step), detector.cycle());
+        }
+    }
+
+    #[test]
+    fn test_pure_cycle() {
+        let detector = CycleDetector::new(0, |x: &u32| (x + 1) % 7);
+        assert_eq!(
+            detector.cycle(),
+            Cycle {
+                start: 0,
+                length: 7
+            }
+        );
+        assert_eq!(*detector.state_after(1_000_000), 1_000_000 % 7);
+    }
+
+    #[test]
+    fn test_state_after() {
+        let detector = CycleDetector::new(3, step);
+        let mut state = 3;
+        for n in 0..1000 {
+            assert_eq!(*detector.state_after(n), state);
+            state = step(&state);
+        }
+    }
+
+    #[test]
+    fn test_accumulate_after() {
+        let detector = CycleDetector::new(3, step);
+        let mut state = 3;
+        let mut total = 0;
+        for n in 0..1000 {
+            assert_eq!(detector.accumulate_after(n, |s| *s), total);
+            total += state;
+            state = step(&state);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::hash::Hash;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+    pub size: usize,
+}
+
+impl Display for MemoStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} evictions, {} entries",
+            self.hits, self.misses, self.evictions, self.size
+        )
+    }
+}
+
+/// Cache of computed values, optionally bounded to a number of entries in
+/// which case the least recently used entry is evicted to make room.
+///
+/// The cache statistics are logged with `aoc::debug!` when it is dropped.
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    name: String,
+    capacity: Option<usize>,
+    // Each entry remembers when it was last used, and a bounded cache keeps
+    // `recency` to map those ticks back to keys so the oldest entry can be
+    // found quickly
+    entries: HashMap<K, (V, u64)>,
+    recency: Option<BTreeMap<u64, K>>,
+    tick: u64,
+    hits: usize,
+    misses: usize,
+    evictions: usize,
+}
+
+impl<K, V> Memo<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            capacity: None,
+            entries: HashMap::new(),
+            recency: None,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Create a cache that holds at most `capacity` entries
+    pub fn with_capacity(name: &str, capacity: usize) -> Self {
+        assert!(capacity > 0, "memo capacity must be positive");
+        let mut memo = Self::new(name);
+        memo.capacity = Some(capacity);
+        memo.recency = Some(BTreeMap::new());
+        memo
+    }
+
+    /// Look up a cached value, counting the lookup as a hit or a miss
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some((value, last_used)) => {
+                self.hits += 1;
+                if let Some(recency) = self.recency.as_mut() {
+                    let key = recency.remove(last_used).unwrap();
+                    recency.insert(self.tick, key);
+                }
+                *last_used = self.tick;
+                Some(value.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        let previous = self.entries.remove(&key);
+        if let (Some(recency), Some(capacity)) = (self.recency.as_mut(), self.capacity) {
+            if let Some((_, last_used)) = previous {
+                recency.remove(&last_used);
+            }
+            while self.entries.len() >= capacity {
+                let (_, oldest) = recency.pop_first().unwrap();
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+            }
+            recency.insert(self.tick, key.clone());
+        }
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    /// Return the cached value for `key`, computing it with `f` on a miss.
+    /// `f` is handed the cache so it can make further (recursive) lookups.
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> V
+    where
+        F: FnOnce(&mut Self, &K) -> V,
+    {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = f(self, &key);
+        self.insert(key, value.clone());
+        value
+    }
+
+    /// Evaluate a recursive function with every call memoised. `f` receives
+    /// a function to recurse through in place of calling itself, e.g.
+    /// `memo.call(n, &|fib, n| if n < 2 { n } else { fib(n - 1) + fib(n - 2) })`
+    pub fn call<F>(&mut self, key: K, f: &F) -> V
+    where
+        F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+    {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = f(&mut |k| self.call(k, f), key.clone());
+        self.insert(key, value.clone());
+        value
+    }
+}
+
+impl<K, V> Memo<K, V> {
+    pub fn stats(&self) -> MemoStats {
+        MemoStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            size: self.entries.len(),
+        }
+    }
+}
+
+impl<K, V> Drop for Memo<K, V> {
+    fn drop(&mut self) {
+        crate::debug!("memo {}: {}", self.name, self.stats());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recursive_call() {
+        let mut memo = Memo::new("fib");
+        let fib = |fib: &mut dyn FnMut(u64) -> u64, n: u64| {
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        };
+        assert_eq!(memo.call(90, &fib), 2880067194370816120);
+        // Each value is computed once and then looked up once more
+        assert_eq!(
+            memo.stats(),
+            MemoStats {
+                hits: 88,
+                misses: 91,
+                evictions: 0,
+                size: 91
+            }
+        );
+        assert!(memo.recency.is_none());
+        assert_eq!(memo.call(50, &fib), 12586269025);
+        assert_eq!(memo.stats().hits, 89);
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        fn count_paths(memo: &mut Memo<(u32, u32), u64>, x: u32, y: u32) -> u64 {
+            memo.get_or_insert_with((x, y), |memo, &(x, y)| {
+                if x == 0 || y == 0 {
+                    1
+                } else {
+                    count_paths(memo, x - 1, y) + count_paths(memo, x, y - 1)
+                }
+            })
+        }
+        let mut memo = Memo::new("paths");
+        assert_eq!(count_paths(&mut memo, 16, 16), 601080390);
+        assert_eq!(memo.stats().size, 17 * 17 - 1);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut memo = Memo::with_capacity("lru", 2);
+        memo.insert(1, "one");
+        memo.insert(2, "two");
+        // Touch 1 so that 2 becomes the least recently used
+        assert_eq!(memo.get(&1), Some("one"));
+        memo.insert(3, "three");
+        assert_eq!(memo.get(&2), None);
+        assert_eq!(memo.get(&1), Some("one"));
+        assert_eq!(memo.get(&3), Some("three"));
+        assert_eq!(
+            memo.stats(),
+            MemoStats {
+                hits: 3,
+                misses: 1,
+                evictions: 1,
+                size: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_bounded_recursion() {
+        // A tiny cache still gives the right answer, just with more misses
+        let mut memo = Memo::with_capacity("fib", 3);
+        let fib = |fib: &mut dyn FnMut(u64) -> u64, n: u64| {
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        };
+        assert_eq!(memo.call(30, &fib), 832040);
+        assert!(memo.stats().size <= 3);
+    }
+}
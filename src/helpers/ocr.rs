@@ -0,0 +1,213 @@
+use array2d::Array2D;
+
+// Fonts used for the block letters in AoC puzzles, each one laid out as a
+// banner so it can be split into glyphs the same way as a puzzle's output.
+// The small font is 6 pixels high with a letter every 5 columns (letters are
+// usually 4 wide, but `Y` fills its cell), the large one 10 high every 8.
+const SMALL_LETTERS: &str = "ABCEFGHIJKLOPRSUYZ";
+const SMALL_FONT: &str = "\
+.##..###...##..####.####..##..#..#..###...##.#..#.#.....##..###..###...###.#..#.#...#####.\n\
+#..#.#..#.#..#.#....#....#..#.#..#...#.....#.#.#..#....#..#.#..#.#..#.#....#..#.#...#...#.\n\
+#..#.###..#....###..###..#....####...#.....#.##...#....#..#.#..#.#..#.#....#..#..#.#...#..\n\
+####.#..#.#....#....#....#.##.#..#...#.....#.#.#..#....#..#.###..###...##..#..#...#...#...\n\
+#..#.#..#.#..#.#....#....#..#.#..#...#..#..#.#.#..#....#..#.#....#.#.....#.#..#...#..#....\n\
+#..#.###...##..####.#.....###.#..#..###..##..#..#.####..##..#....#..#.###...##....#..####.";
+
+const LARGE_LETTERS: &str = "ABCEFGHJKLNPRXZ";
+const LARGE_FONT: &str = "\
+..##....#####....####...######..######...####...#....#.....###..#....#..#.......#....#..#####...#####...#....#..######..\n\
+.#..#...#....#..#....#..#.......#.......#....#..#....#......#...#...#...#.......##...#..#....#..#....#..#....#.......#..\n\
+#....#..#....#..#.......#.......#.......#.......#....#......#...#..#....#.......##...#..#....#..#....#...#..#........#..\n\
+#....#..#....#..#.......#.......#.......#.......#....#......#...#.#.....#.......#.#..#..#....#..#....#...#..#.......#...\n\
+#....#..#####...#.......#####...#####...#.......######......#...##......#.......#.#..#..#####...#####.....##.......#....\n\
+######..#....#..#.......#.......#.......#..###..#....#......#...##......#.......#..#.#..#.......#..#......##......#.....\n\
+#....#..#....#..#.......#.......#.......#....#..#....#......#...#.#.....#.......#..#.#..#.......#...#....#..#....#......\n\
+#....#..#....#..#.......#.......#.......#....#..#....#..#...#...#..#....#.......#...##..#.......#...#....#..#...#.......\n\
+#....#..#....#..#....#..#.......#.......#...##..#....#..#...#...#...#...#.......#...##..#.......#....#..#....#..#.......\n\
+#....#..#####....####...######..#........###.#..#....#...###....#....#..######..#....#..#.......#....#..#....#..######..";
+
+const SMALL_PITCH: usize = 5;
+const LARGE_PITCH: usize = 8;
+
+type Image = Vec<Vec<bool>>;
+
+/// Read the block letters in an image drawn with `#` for lit pixels and
+/// anything else (usually `.`) for dark ones
+pub fn recognise(image: &str) -> Result<String, String> {
+    let image = image
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect();
+    recognise_image(image)
+}
+
+pub fn recognise_grid(grid: &Array2D<bool>) -> Result<String, String> {
+    recognise_image(grid.as_rows())
+}
+
+fn recognise_image(image: Image) -> Result<String, String> {
+    let image = trim_rows(image);
+    let (font, pitch) = match image.len() {
+        6 => (font(SMALL_LETTERS, SMALL_FONT, SMALL_PITCH), SMALL_PITCH),
+        10 => (font(LARGE_LETTERS, LARGE_FONT, LARGE_PITCH), LARGE_PITCH),
+        0 => return Err("image has no lit pixels".to_owned()),
+        height => return Err(format!("no font is {} pixels high", height)),
+    };
+    let read = |origin: isize| -> Result<String, String> {
+        glyphs(&image, origin, pitch)
+            .into_iter()
+            .map(|(column, glyph)| {
+                font.iter()
+                    .find(|(_, g)| *g == glyph)
+                    .map(|(letter, _)| *letter)
+                    .ok_or(format!("unrecognised glyph at column {}", column))
+            })
+            .collect()
+    };
+    // The cells usually start at the first lit column, but a letter with
+    // blank columns on its left (like `I`) hides where its cell starts
+    let first = (0..image[0].len())
+        .find(|&col| image.iter().any(|row| row.get(col) == Some(&true)))
+        .unwrap_or(0) as isize;
+    (0..pitch as isize)
+        .map(|shift| read(first - shift))
+        .find(Result::is_ok)
+        .unwrap_or_else(|| read(first))
+}
+
+fn font(letters: &str, banner: &str, pitch: usize) -> Vec<(char, Image)> {
+    let image = banner
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect::<Image>();
+    letters
+        .chars()
+        .zip(glyphs(&image, 0, pitch).into_iter().map(|(_, glyph)| glyph))
+        .collect()
+}
+
+// Drop blank rows above and below the letters
+fn trim_rows(mut image: Image) -> Image {
+    let lit = |row: &Vec<bool>| row.iter().any(|&p| p);
+    while image.first().is_some_and(|row| !lit(row)) {
+        image.remove(0);
+    }
+    while image.last().is_some_and(|row| !lit(row)) {
+        image.pop();
+    }
+    image
+}
+
+/// Split an image into cells `pitch` columns wide starting at `origin`,
+/// cropping each to its lit columns and skipping blank ones. Each glyph
+/// comes with the column it starts at.
+fn glyphs(image: &Image, origin: isize, pitch: usize) -> Vec<(usize, Image)> {
+    let width = image.iter().map(|row| row.len()).max().unwrap_or(0) as isize;
+    let pixel = |row: usize, col: isize| {
+        usize::try_from(col).is_ok_and(|col| image[row].get(col).copied().unwrap_or(false))
+    };
+    let column_lit = |col: isize| (0..image.len()).any(|row| pixel(row, col));
+
+    let mut result = Vec::new();
+    let mut cell = origin;
+    while cell < width {
+        let lit = (cell..cell + pitch as isize)
+            .filter(|&col| column_lit(col))
+            .collect::<Vec<_>>();
+        if let (Some(&start), Some(&end)) = (lit.first(), lit.last()) {
+            let glyph = (0..image.len())
+                .map(|row| (start..=end).map(|col| pixel(row, col)).collect())
+                .collect();
+            result.push((start as usize, glyph));
+        }
+        cell += pitch as isize;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_font() {
+        assert_eq!(recognise(SMALL_FONT), Ok(SMALL_LETTERS.to_owned()));
+        let image = "\
+.##..###..#..#.####.
+#..#.#..#.#.#..#....
+#..#.#..#.##...###..
+####.###..#.#..#....
+#..#.#.#..#.#..#....
+#..#.#..#.#..#.####.";
+        assert_eq!(recognise(image), Ok("ARKE".to_owned()));
+    }
+
+    #[test]
+    fn test_touching_letters() {
+        // `Y` fills its whole cell, so nothing separates it from the next letter
+        let image = "\
+#...#####.
+#...#...#.
+.#.#...#..
+..#...#...
+..#..#....
+..#..####.";
+        assert_eq!(recognise(image), Ok("YZ".to_owned()));
+        // A leading `I` doesn't start at the edge of its cell
+        let image = "\
+.###.#...#
+..#..#...#
+..#...#.#.
+..#....#..
+..#....#..
+.###...#..";
+        assert_eq!(recognise(image), Ok("IY".to_owned()));
+    }
+
+    #[test]
+    fn test_large_font() {
+        assert_eq!(recognise(LARGE_FONT), Ok(LARGE_LETTERS.to_owned()));
+    }
+
+    #[test]
+    fn test_recognise_grid() {
+        let image = "\
+...........
+.###..#....
+.#..#.#....
+.#..#.#....
+.###..#....
+.#....#....
+.#....####.
+...........";
+        let rows = image
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect::<Vec<Vec<bool>>>();
+        let grid = Array2D::from_rows(&rows).unwrap();
+        assert_eq!(recognise_grid(&grid), Ok("PL".to_owned()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            recognise("...\n..."),
+            Err("image has no lit pixels".to_owned())
+        );
+        assert_eq!(
+            recognise("#\n#\n#"),
+            Err("no font is 3 pixels high".to_owned())
+        );
+        let image = "\
+####..#.
+#.....#.
+###...#.
+#.....#.
+#.....#.
+#.....#.";
+        assert_eq!(
+            recognise(image),
+            Err("unrecognised glyph at column 6".to_owned())
+        );
+    }
+}
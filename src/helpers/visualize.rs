@@ -0,0 +1,298 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use array2d::Array2D;
+
+/// A grid cell that knows how to draw itself
+pub trait Pixel {
+    fn glyph(&self) -> char;
+
+    /// Colour used in image output, by default anything but blank space is lit
+    fn colour(&self) -> [u8; 3] {
+        match self.glyph() {
+            ' ' | '.' => [0x0f, 0x0f, 0x23],
+            _ => [0xff, 0xff, 0x66],
+        }
+    }
+}
+
+impl Pixel for char {
+    fn glyph(&self) -> char {
+        *self
+    }
+}
+
+impl Pixel for bool {
+    fn glyph(&self) -> char {
+        if *self {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+type GifEncoder = gif::Encoder<BufWriter<File>>;
+
+enum Target {
+    Terminal,
+    Ppm(PathBuf),
+    Png(PathBuf),
+    // The encoder is made for the size of the first frame
+    Gif(PathBuf, Option<(GifEncoder, (u16, u16))>),
+}
+
+/// Renders frames emitted by a solution. Solutions only get a visualizer
+/// when run with `--visualize`, so the timed runs never draw anything.
+///
+/// Options:
+///   --output <path>  write frames to `.ppm`/`.png` files (numbered) or an
+///                    animated `.gif` rather than the terminal
+///   --fps <n>        frame rate limit in the terminal, gif playback speed
+///   --every <n>      only draw every nth frame
+///   --scale <n>      size in pixels of one grid cell in images
+pub struct Visualizer {
+    target: Target,
+    frame_time: Duration,
+    every: usize,
+    scale: usize,
+    emitted: usize,
+    drawn: usize,
+    last_drawn: Option<Instant>,
+}
+
+impl Visualizer {
+    pub fn from_args() -> Option<Visualizer> {
+        let mut args = pico_args::Arguments::from_env();
+        if !args.contains("--visualize") {
+            return None;
+        }
+        let output: Option<PathBuf> = args.opt_value_from_str("--output").unwrap();
+        let fps: u32 = args.opt_value_from_str("--fps").unwrap().unwrap_or(30);
+        let every: usize = args.opt_value_from_str("--every").unwrap().unwrap_or(1);
+        let scale: usize = args.opt_value_from_str("--scale").unwrap().unwrap_or(4);
+        let mut visualizer = match output {
+            Some(path) => Visualizer::to_file(&path),
+            None => Visualizer::terminal(),
+        };
+        visualizer.frame_time = Duration::from_secs(1) / fps.max(1);
+        visualizer.every = every.max(1);
+        visualizer.scale = scale.max(1);
+        Some(visualizer)
+    }
+
+    pub fn terminal() -> Visualizer {
+        Visualizer {
+            target: Target::Terminal,
+            frame_time: Duration::from_secs(1) / 30,
+            every: 1,
+            scale: 4,
+            emitted: 0,
+            drawn: 0,
+            last_drawn: None,
+        }
+    }
+
+    /// Write frames to files, the extension of `path` picks the format
+    pub fn to_file(path: &Path) -> Visualizer {
+        let target = match path.extension().and_then(|e| e.to_str()) {
+            Some("ppm") => Target::Ppm(path.to_owned()),
+            Some("png") => Target::Png(path.to_owned()),
+            Some("gif") => Target::Gif(path.to_owned(), None),
+            _ => panic!("unsupported visualization output {:?}", path),
+        };
+        Visualizer {
+            target,
+            ..Visualizer::terminal()
+        }
+    }
+
+    /// Number of frames drawn so far
+    pub fn frames(&self) -> usize {
+        self.drawn
+    }
+
+    pub fn frame<P: Pixel>(&mut self, grid: &Array2D<P>) {
+        self.emitted += 1;
+        if !(self.emitted - 1).is_multiple_of(self.every) {
+            return;
+        }
+        match &mut self.target {
+            Target::Terminal => {
+                if let Some(last) = self.last_drawn {
+                    if let Some(wait) = self.frame_time.checked_sub(last.elapsed()) {
+                        sleep(wait);
+                    }
+                }
+                // Clear the screen and move the cursor home before drawing
+                print!("\x1b[2J\x1b[H{}", to_text(grid));
+                std::io::stdout().flush().unwrap();
+            }
+            Target::Ppm(path) => {
+                let (width, height, rgb) = to_rgb(grid, self.scale);
+                let mut file = create(&numbered(path, self.drawn));
+                write!(file, "P6\n{} {}\n255\n", width, height).unwrap();
+                file.write_all(&rgb).unwrap();
+            }
+            Target::Png(path) => {
+                let (width, height, rgb) = to_rgb(grid, self.scale);
+                let file = create(&numbered(path, self.drawn));
+                let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header().unwrap();
+                writer.write_image_data(&rgb).unwrap();
+            }
+            Target::Gif(path, encoder) => {
+                let (width, height, rgb) = to_rgb(grid, self.scale);
+                let (width, height) = (width as u16, height as u16);
+                let (encoder, size) = encoder.get_or_insert_with(|| {
+                    let mut encoder = gif::Encoder::new(create(path), width, height, &[]).unwrap();
+                    encoder.set_repeat(gif::Repeat::Infinite).unwrap();
+                    (encoder, (width, height))
+                });
+                assert!(
+                    *size == (width, height),
+                    "gif frame {} is {}x{} but the first was {}x{}",
+                    self.drawn,
+                    width,
+                    height,
+                    size.0,
+                    size.1
+                );
+                let (palette, indices) = to_palette(&rgb);
+                let mut frame =
+                    gif::Frame::from_palette_pixels(width, height, &indices, &palette, None);
+                // Gif delays are in hundredths of a second
+                frame.delay = (self.frame_time.as_millis() / 10).max(1) as u16;
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        self.drawn += 1;
+        self.last_drawn = Some(Instant::now());
+    }
+}
+
+fn create(path: &Path) -> BufWriter<File> {
+    let file = File::create(path).unwrap_or_else(|e| panic!("could not create {:?}: {}", path, e));
+    BufWriter::new(file)
+}
+
+// Turn `frames/sand.png` into `frames/sand_0007.png`
+fn numbered(path: &Path, frame: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    let ext = path.extension().unwrap().to_string_lossy();
+    path.with_file_name(format!("{}_{:04}.{}", stem, frame, ext))
+}
+
+pub fn to_text<P: Pixel>(grid: &Array2D<P>) -> String {
+    grid.rows_iter()
+        .map(|row| row.map(|p| p.glyph()).collect::<String>() + "\n")
+        .collect()
+}
+
+/// Scale a grid up into rgb bytes, returning the image width and height too
+fn to_rgb<P: Pixel>(grid: &Array2D<P>, scale: usize) -> (usize, usize, Vec<u8>) {
+    let (width, height) = (grid.num_columns() * scale, grid.num_rows() * scale);
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for row in grid.rows_iter() {
+        let line = row
+            .flat_map(|p| p.colour().repeat(scale))
+            .collect::<Vec<u8>>();
+        for _ in 0..scale {
+            rgb.extend_from_slice(&line);
+        }
+    }
+    (width, height, rgb)
+}
+
+/// Split rgb bytes into a palette and an index into it for every pixel
+fn to_palette(rgb: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut colours: Vec<&[u8]> = Vec::new();
+    let indices = rgb
+        .chunks(3)
+        .map(|c| match colours.iter().position(|&p| p == c) {
+            Some(idx) => idx as u8,
+            None => {
+                assert!(colours.len() < 256, "too many colours for a gif frame");
+                colours.push(c);
+                (colours.len() - 1) as u8
+            }
+        })
+        .collect();
+    (colours.concat(), indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Array2D<bool> {
+        Array2D::from_rows(&[vec![true, false, false], vec![false, true, true]]).unwrap()
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(to_text(&sample()), "#..\n.##\n");
+    }
+
+    #[test]
+    fn test_to_rgb() {
+        let (width, height, rgb) = to_rgb(&sample(), 2);
+        assert_eq!((width, height), (6, 4));
+        assert_eq!(rgb.len(), 6 * 4 * 3);
+        // Top left cell is lit across both scaled rows, bottom left is not
+        assert_eq!(&rgb[0..6], &[0xff, 0xff, 0x66, 0xff, 0xff, 0x66]);
+        assert_eq!(&rgb[18..24], &[0xff, 0xff, 0x66, 0xff, 0xff, 0x66]);
+        assert_eq!(&rgb[36..39], &[0x0f, 0x0f, 0x23]);
+    }
+
+    #[test]
+    fn test_to_palette() {
+        let (palette, indices) = to_palette(&[1, 2, 3, 4, 5, 6, 1, 2, 3]);
+        assert_eq!(palette, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(indices, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn test_numbered() {
+        assert_eq!(
+            numbered(Path::new("frames/sand.png"), 7),
+            PathBuf::from("frames/sand_0007.png")
+        );
+    }
+
+    #[test]
+    fn test_file_outputs() {
+        let dir = std::env::temp_dir().join(format!("aoc_visualize_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["grid.ppm", "grid.png", "grid.gif"] {
+            let mut visualizer = Visualizer::to_file(&dir.join(name));
+            visualizer.frame(&sample());
+            visualizer.frame(&sample());
+            assert_eq!(visualizer.frames(), 2);
+        }
+        let ppm = std::fs::read(dir.join("grid_0001.ppm")).unwrap();
+        assert!(ppm.starts_with(b"P6\n12 8\n255\n"));
+        let png = std::fs::read(dir.join("grid_0000.png")).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let gif = std::fs::read(dir.join("grid.gif")).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "gif frame 1 is 12x4 but the first was 12x8")]
+    fn test_gif_frame_size() {
+        let path = std::env::temp_dir().join(format!("aoc_resized_{}.gif", std::process::id()));
+        let mut visualizer = Visualizer::to_file(&path);
+        visualizer.frame(&sample());
+        let row = Array2D::from_rows(&[vec![true, false, true]]).unwrap();
+        // Tidy up first, as the next frame panics
+        std::fs::remove_file(&path).unwrap();
+        visualizer.frame(&row);
+    }
+}
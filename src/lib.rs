@@ -7,6 +7,7 @@ use std::env;
 use std::fs;
 
 pub mod helpers;
+pub mod log;
 
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
@@ -17,21 +18,30 @@ macro_rules! solve {
     ($part:expr, $solver:ident, $input:expr) => {{
         use aoc::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
         use std::fmt::Display;
+        use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
         use std::time::Instant;
 
         fn print_result<T: Display>(func: impl FnOnce(&str) -> Option<T>, input: &str) {
+            // Anything the part logs is only shown if it fails
+            aoc::log::start_capture();
             let timer = Instant::now();
-            let result = func(input);
+            let result = catch_unwind(AssertUnwindSafe(|| func(input)));
             let elapsed = timer.elapsed();
+            let log = aoc::log::finish_capture();
             match result {
-                Some(result) => {
+                Ok(Some(result)) => {
                     println!(
                         "{} {}(elapsed: {:.2?}){}",
                         result, ANSI_ITALIC, elapsed, ANSI_RESET
                     );
                 }
-                None => {
-                    println!("not solved.")
+                Ok(None) => {
+                    println!("not solved.");
+                    aoc::log::replay(&log);
+                }
+                Err(panic) => {
+                    aoc::log::replay(&log);
+                    resume_unwind(panic);
                 }
             }
         }
@@ -0,0 +1,127 @@
+/*
+ * Logging for solutions: `aoc::debug!` and `aoc::trace!` only record anything in debug builds
+ * with `--verbose` (or `-v`, twice for trace) or `AOC_LOG=debug|trace` set, so benchmark runs
+ * stay silent. While a part runs under `solve!` its log is captured and only shown if it fails.
+ */
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Level {
+    Debug = 1,
+    Trace = 2,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Debug => write!(f, "debug"),
+            Level::Trace => write!(f, "trace"),
+        }
+    }
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+fn parse_verbosity(value: &str) -> Option<u8> {
+    match value.to_lowercase().as_str() {
+        "" | "0" | "off" => Some(0),
+        "1" | "debug" => Some(Level::Debug as u8),
+        "2" | "trace" => Some(Level::Trace as u8),
+        _ => None,
+    }
+}
+
+/// How much to log, `AOC_LOG` wins over the command line flags
+pub fn verbosity() -> u8 {
+    static VERBOSITY: OnceLock<u8> = OnceLock::new();
+    *VERBOSITY.get_or_init(|| {
+        if let Ok(value) = std::env::var("AOC_LOG") {
+            return parse_verbosity(&value).unwrap_or_else(|| {
+                eprintln!("ignoring unknown AOC_LOG level \"{}\"", value);
+                0
+            });
+        }
+        let mut args = pico_args::Arguments::from_env();
+        let mut verbosity = 0;
+        while args.contains(["-v", "--verbose"]) {
+            verbosity += 1;
+        }
+        verbosity
+    })
+}
+
+pub fn enabled(level: Level) -> bool {
+    cfg!(debug_assertions) && level as u8 <= verbosity()
+}
+
+/// Log a message, holding on to it if a capture is running on this thread
+pub fn record(level: Level, message: String) {
+    let line = format!("[{}] {}", level, message);
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(lines) => lines.push(line),
+        None => eprintln!("{}", line),
+    });
+}
+
+pub fn start_capture() {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+}
+
+/// Stop capturing and return everything logged since `start_capture`
+pub fn finish_capture() -> Vec<String> {
+    CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default())
+}
+
+pub fn replay(lines: &[String]) {
+    for line in lines {
+        eprintln!("{}", line);
+    }
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            $crate::log::record($crate::log::Level::Debug, format!($($arg)*));
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Trace) {
+            $crate::log::record($crate::log::Level::Trace, format!($($arg)*));
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verbosity() {
+        assert_eq!(parse_verbosity("off"), Some(0));
+        assert_eq!(parse_verbosity("Debug"), Some(1));
+        assert_eq!(parse_verbosity("2"), Some(2));
+        assert_eq!(parse_verbosity("loud"), None);
+    }
+
+    #[test]
+    fn test_capture() {
+        start_capture();
+        record(Level::Debug, "first".to_owned());
+        record(Level::Trace, format!("second {}", 2));
+        assert_eq!(
+            finish_capture(),
+            vec!["[debug] first".to_owned(), "[trace] second 2".to_owned()]
+        );
+        // Nothing is captured once finished
+        assert_eq!(finish_capture(), Vec::<String>::new());
+    }
+}